@@ -0,0 +1,372 @@
+use std::time::{Duration, Instant};
+
+use wgpu::SurfaceError;
+use winit::{
+    dpi::PhysicalSize,
+    event::Event,
+    event_loop::{ControlFlow, EventLoop},
+};
+
+use egui_blur::{RenderContext, WindowTexture, SHADER_SOURCE_DIR};
+
+use crate::{
+    schedule::{Due, RedrawSchedule},
+    surface::SurfaceRenderer,
+};
+
+pub struct Program {
+    window: winit::window::Window,
+    event_loop: Option<EventLoop<()>>,
+
+    render_ctx: RenderContext,
+    surface: SurfaceRenderer,
+    window_texture: WindowTexture,
+
+    egui_ctx: egui::Context,
+    egui_winit_bridge: egui_winit::State,
+    egui_wgpu_renderer: egui_wgpu::Renderer,
+
+    ferris_img: egui::TextureHandle,
+
+    /// set by F12, the next frame is saved once drawn
+    screenshot_requested: bool,
+
+    schedule: RedrawSchedule,
+}
+
+/// Shader edits show up within this long, while the UI is idle.
+const SHADER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Set to watch the shader sources, e.g. `EGUI_BLUR_WATCH_SHADERS=1 cargo
+/// run --example demo`.
+const WATCH_SHADERS_VAR: &str = "EGUI_BLUR_WATCH_SHADERS";
+
+impl Program {
+    pub async fn new() -> Self {
+        let event_loop = EventLoop::new();
+        let window = winit::window::WindowBuilder::new()
+            .with_inner_size(winit::dpi::LogicalSize::new(455., 330.))
+            .with_title("Blur Rect Demo")
+            .build(&event_loop)
+            .unwrap();
+
+        let mut render_ctx = RenderContext::new().await;
+
+        // edits to the shaders show up as they are saved, if asked for, the
+        // loop otherwise sleeps until there's input
+        let watch_shaders = std::env::var_os(WATCH_SHADERS_VAR).is_some();
+        let shader_poll_interval = watch_shaders.then(|| {
+            render_ctx.watch_shaders(SHADER_SOURCE_DIR);
+            SHADER_POLL_INTERVAL
+        });
+
+        let surface = SurfaceRenderer::from_window(&window, &render_ctx);
+
+        let window_texture = Self::create_window_texture(&render_ctx, &surface);
+
+        let egui_ctx = egui::Context::default();
+        // the frames of egui's own popups, such as the color picker's, are
+        // blurred too
+        egui_blur::set_blur_style(&egui_ctx, egui_blur::BlurStyle::default());
+
+        let egui_wgpu_renderer =
+            egui_wgpu::Renderer::new(render_ctx.get_device().0, surface.format(), None, 1);
+
+        let mut egui_winit_bridge = egui_winit::State::new(&window);
+        egui_winit_bridge.set_pixels_per_point(window.scale_factor() as f32);
+
+        let image = image::load_from_memory(include_bytes!("cuddlyferris.png").as_slice()).unwrap();
+        let size = [image.width() as _, image.height() as _];
+        let image_buffer = image.to_rgba8();
+        let pixels = image_buffer.as_flat_samples();
+
+        let ferris_img = egui_ctx.load_texture(
+            "cuddlyferris",
+            egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice()),
+            egui::TextureOptions::LINEAR,
+        );
+
+        Self {
+            window,
+            event_loop: Some(event_loop),
+
+            render_ctx,
+            surface,
+            window_texture,
+
+            egui_ctx,
+            egui_wgpu_renderer,
+            egui_winit_bridge,
+
+            ferris_img,
+
+            screenshot_requested: false,
+
+            // the first frame is drawn as soon as the window is shown
+            schedule: RedrawSchedule::new(Instant::now(), shader_poll_interval),
+        }
+    }
+
+    pub fn run(mut self) {
+        let event_loop = self.event_loop.take().unwrap();
+
+        event_loop.run(move |event, _, control_flow| {
+            let due = self.schedule.on_event(&event, Instant::now(), control_flow);
+            self.request_redraw_if(due);
+
+            match event {
+                Event::WindowEvent { event, .. } => {
+                    self.handle_window_event(&event, control_flow);
+                }
+
+                Event::RedrawRequested(..) => {
+                    self.handle_redraw_request(control_flow);
+                }
+
+                _ => (),
+            }
+        })
+    }
+
+    /// Picks up the shaders if they were edited, reporting why they or the
+    /// pipelines can't be, and returns whether they changed.
+    fn reload_shaders(&mut self) -> bool {
+        match self.render_ctx.reload_shaders() {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(err) => {
+                eprintln!("shaders not reloaded:\n{err}");
+                return false;
+            }
+        }
+
+        let (device, shaders) = (&self.render_ctx.device, &self.render_ctx.shaders);
+        if let Err(err) = self.window_texture.reload_pipelines(device, shaders) {
+            eprintln!("pipelines not rebuilt:\n{err}");
+        }
+
+        true
+    }
+
+    /// Requests a redraw if egui's repaint is due or the shaders changed. The
+    /// schedule sleeps until either is due again once the frame is drawn,
+    /// with nothing animating only input wakes the loop up.
+    fn request_redraw_if(&mut self, due: Due) {
+        if due.poll_shaders && self.reload_shaders() {
+            self.window.request_redraw();
+        }
+
+        if due.repaint {
+            self.window.request_redraw();
+        }
+    }
+
+    fn resize(&mut self, new_inner_size: PhysicalSize<u32>, new_scale_factor: Option<f64>) {
+        if let Some(new_scale_factor) = new_scale_factor {
+            self.surface.set_scale_factor(new_scale_factor)
+        }
+
+        self.surface.resize(new_inner_size, &self.render_ctx);
+        self.window_texture.resize(
+            &self.render_ctx.device,
+            &self.render_ctx.queue,
+            &self.surface.render_target(),
+        );
+    }
+
+    fn create_window_texture(
+        render_ctx: &RenderContext,
+        surface: &SurfaceRenderer,
+    ) -> WindowTexture {
+        WindowTexture::new(
+            &render_ctx.device,
+            &render_ctx.shaders,
+            &surface.render_target(),
+        )
+    }
+
+    pub fn handle_window_event(
+        &mut self,
+        event: &winit::event::WindowEvent,
+        control_flow: &mut ControlFlow,
+    ) {
+        match event {
+            winit::event::WindowEvent::Resized(new_size) => {
+                self.resize(*new_size, None);
+                self.window.request_redraw();
+            }
+
+            winit::event::WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_inner_size,
+            } => {
+                self.egui_winit_bridge
+                    .set_pixels_per_point(*scale_factor as f32);
+                self.resize(**new_inner_size, Some(*scale_factor));
+                self.window.request_redraw();
+            }
+
+            _ => (),
+        }
+
+        let response = self.egui_winit_bridge.on_event(&self.egui_ctx, event);
+
+        if response.repaint {
+            self.window.request_redraw();
+        }
+
+        if !response.consumed {
+            match event {
+                winit::event::WindowEvent::KeyboardInput {
+                    input:
+                        winit::event::KeyboardInput {
+                            virtual_keycode: Some(winit::event::VirtualKeyCode::Q),
+                            ..
+                        },
+                    ..
+                }
+                | winit::event::WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+
+                winit::event::WindowEvent::KeyboardInput {
+                    input:
+                        winit::event::KeyboardInput {
+                            virtual_keycode: Some(winit::event::VirtualKeyCode::F12),
+                            state: winit::event::ElementState::Pressed,
+                            ..
+                        },
+                    ..
+                } => {
+                    self.screenshot_requested = true;
+                    self.window.request_redraw();
+                }
+
+                _ => (),
+            }
+        }
+    }
+
+    pub fn handle_redraw_request(&mut self, control_flow: &mut ControlFlow) {
+        match self.draw() {
+            Ok(_) => {}
+            Err(SurfaceError::Lost) => {
+                self.surface.reconfigure(&self.render_ctx);
+                self.window.request_redraw();
+            }
+            Err(SurfaceError::OutOfMemory) => *control_flow = ControlFlow::ExitWithCode(1),
+            Err(e) => eprintln!("{:?}", e),
+        }
+    }
+
+    pub fn draw(&mut self) -> Result<(), SurfaceError> {
+        let raw_input = self.egui_winit_bridge.take_egui_input(&self.window);
+
+        let mut full_output = self
+            .egui_ctx
+            .run(raw_input, |ctx| crate::ui::ui_main(ctx, &self.ferris_img));
+        egui_blur::blur_frames(&self.egui_ctx, &mut full_output.shapes);
+
+        self.schedule
+            .frame_drawn(Instant::now(), full_output.repaint_after);
+
+        let mut paint_jobs = self.egui_ctx.tessellate(full_output.shapes);
+        let screen_descriptor = self.surface.render_target().screen_descriptor();
+
+        let (device, queue) = self.render_ctx.get_device();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("EGUI Render Encoder"),
+        });
+
+        for (id, image_delta) in &full_output.textures_delta.set {
+            self.egui_wgpu_renderer
+                .update_texture(device, queue, *id, image_delta)
+        }
+
+        // the cached backdrops may have been painted with the old textures
+        if !full_output.textures_delta.set.is_empty() {
+            self.window_texture.invalidate_cache();
+        }
+
+        self.window_texture
+            .prepare(device, queue, &mut encoder, &mut paint_jobs);
+
+        self.egui_wgpu_renderer.update_buffers(
+            device,
+            queue,
+            &mut encoder,
+            &paint_jobs,
+            &screen_descriptor,
+        );
+
+        self.window_texture.render(
+            &mut encoder,
+            &self.egui_wgpu_renderer,
+            &mut paint_jobs,
+            &screen_descriptor,
+        );
+
+        let output = self.surface.get_current_texture(&self.render_ctx)?;
+
+        {
+            let surface_view = output
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default());
+
+            let mut copy_render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("EGUI copy render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &surface_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            self.window_texture.copy_to(&mut copy_render_pass);
+        }
+
+        queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+
+        if std::mem::take(&mut self.screenshot_requested) {
+            self.save_screenshot();
+        }
+
+        for id in &full_output.textures_delta.free {
+            self.egui_wgpu_renderer.free_texture(id);
+        }
+
+        self.egui_winit_bridge.handle_platform_output(
+            &self.window,
+            &self.egui_ctx,
+            full_output.platform_output,
+        );
+
+        Ok(())
+    }
+
+    fn save_screenshot(&self) {
+        let (device, queue) = self.render_ctx.get_device();
+
+        let Some(image) = self.window_texture.read_back(device, queue) else {
+            eprintln!(
+                "can't take screenshots of {:?} surfaces",
+                self.surface.format()
+            );
+            return;
+        };
+
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let path = format!("screenshot-{secs}.png");
+
+        match image.save(&path) {
+            Ok(()) => println!("saved {path}"),
+            Err(e) => eprintln!("failed to save {path}: {e}"),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::time::{Duration, Instant};
+
+use winit::{event::Event, event_loop::ControlFlow};
+
+/// When the demo redraws and when its event loop wakes up without input: for
+/// egui's `repaint_after`, and to poll the shader sources for edits if they
+/// are watched.
+pub struct RedrawSchedule {
+    /// when egui asked to be run again without any input, e.g. to animate
+    repaint_at: Option<Instant>,
+    /// how often the shader sources are checked for edits, if at all
+    shader_poll_interval: Option<Duration>,
+    /// when they're checked next
+    next_shader_poll: Option<Instant>,
+}
+
+/// What the loop is due to do once it's through with the input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Due {
+    pub poll_shaders: bool,
+    pub repaint: bool,
+}
+
+impl RedrawSchedule {
+    /// The first frame is due at `now`, the shaders are first polled an
+    /// interval later.
+    pub fn new(now: Instant, shader_poll_interval: Option<Duration>) -> Self {
+        Self {
+            repaint_at: Some(now),
+            shader_poll_interval,
+            next_shader_poll: shader_poll_interval.map(|interval| now + interval),
+        }
+    }
+
+    /// Takes egui's `repaint_after` from a frame drawn at `now`.
+    pub fn frame_drawn(&mut self, now: Instant, repaint_after: Duration) {
+        // `Duration::MAX` when nothing animates, which no instant is that far
+        // ahead of
+        self.repaint_at = now.checked_add(repaint_after);
+    }
+
+    /// Whether egui's repaint is due at `now`, which it then no longer is.
+    pub fn repaint_due(&mut self, now: Instant) -> bool {
+        let due = self.repaint_at.is_some_and(|repaint_at| repaint_at <= now);
+        if due {
+            self.repaint_at = None;
+        }
+
+        due
+    }
+
+    /// Whether the shaders are due to be polled at `now`, scheduling the next
+    /// poll an interval later if they are. The loop wakes up for every bit of
+    /// input too, which mustn't poll them any more often.
+    pub fn shader_poll_due(&mut self, now: Instant) -> bool {
+        let (Some(interval), Some(next)) = (self.shader_poll_interval, self.next_shader_poll)
+        else {
+            return false;
+        };
+
+        if now < next {
+            return false;
+        }
+
+        self.next_shader_poll = Some(now + interval);
+        true
+    }
+
+    /// Follows the loop through `event` at `now`. Once the input is handled
+    /// it tells what is due, and once the frames are drawn, and egui's
+    /// `repaint_after` known, it sleeps until the next wake up.
+    pub fn on_event<T>(
+        &mut self,
+        event: &Event<T>,
+        now: Instant,
+        control_flow: &mut ControlFlow,
+    ) -> Due {
+        match event {
+            Event::MainEventsCleared => Due {
+                poll_shaders: self.shader_poll_due(now),
+                repaint: self.repaint_due(now),
+            },
+            Event::RedrawEventsCleared => {
+                if !matches!(control_flow, ControlFlow::ExitWithCode(_)) {
+                    *control_flow = match self.wake_up() {
+                        Some(wake_up) => ControlFlow::WaitUntil(wake_up),
+                        None => ControlFlow::Wait,
+                    };
+                }
+
+                Due::default()
+            }
+            _ => Due::default(),
+        }
+    }
+
+    /// When the loop is woken up next without any input, `None` to only wait
+    /// for input.
+    pub fn wake_up(&self) -> Option<Instant> {
+        self.repaint_at
+            .into_iter()
+            .chain(self.next_shader_poll)
+            .min()
+    }
+}
@@ -1,4 +1,4 @@
-use crate::context::RenderContext;
+use egui_blur::{RenderContext, RenderTarget};
 
 pub(crate) type SurfaceError = wgpu::SurfaceError;
 
@@ -24,7 +24,7 @@ impl SurfaceRenderer {
             config,
         };
 
-        res.configure(&ctx);
+        res.configure(ctx);
 
         res
     }
@@ -64,7 +64,7 @@ impl SurfaceRenderer {
             self.size = new_size;
             self.set_width(new_size.width);
             self.set_height(new_size.height);
-            self.configure(&context);
+            self.configure(context);
         }
     }
 
@@ -80,18 +80,11 @@ impl SurfaceRenderer {
         self.size
     }
 
-    pub fn screen_descriptor(&self) -> egui_wgpu::renderer::ScreenDescriptor {
-        egui_wgpu::renderer::ScreenDescriptor {
-            pixels_per_point: self.scale_fac as f32,
-            size_in_pixels: self.size.into(),
+    pub fn render_target(&self) -> RenderTarget {
+        RenderTarget {
+            format: self.format(),
+            size: self.size,
+            pixels_per_point: self.scale_fac,
         }
     }
-
-    pub fn logical_size(&self) -> winit::dpi::LogicalSize<f64> {
-        return self.size().to_logical(self.scale_fac);
-    }
-
-    pub fn get_scale_fac(&self) -> f64 {
-        self.scale_fac
-    }
 }
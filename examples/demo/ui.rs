@@ -0,0 +1,188 @@
+use egui::*;
+
+use egui_blur::{BackdropFilter, BlurMode, BlurPanel, BlurParams, BlurPopup, BlurWindow};
+
+pub fn ui_main(ctx: &egui::Context, image: &egui::TextureHandle) {
+    // painted before the menu bar, which blurs what's under it
+    let painter = ctx.layer_painter(LayerId::background());
+    let screen = ctx.screen_rect();
+    painter.rect_filled(screen, 0.0, ctx.style().visuals.panel_fill);
+    painter.image(
+        image.id(),
+        Rect::from_min_size(screen.min, image.size_vec2()),
+        Rect::from_min_max(pos2(0., 0.), pos2(1., 1.)),
+        Color32::WHITE,
+    );
+
+    // a frosted badge in the image's bottom right corner, cut to a circle
+    let badge = Shape::circle_filled(
+        screen.min + image.size_vec2() - vec2(60., 60.),
+        40.,
+        Color32::WHITE,
+    );
+    painter.add(egui_blur::shape_blur_callback(badge, BlurParams::default()));
+
+    let popup = BlurPopup::new();
+    menu_bar(ctx, &popup);
+
+    egui::CentralPanel::default()
+        .frame(Frame::none())
+        .show(ctx, |ui| {
+            let response = ui.interact(ui.max_rect(), ui.id(), Sense::click());
+            popup.context_menu(response, reset_button);
+        });
+
+    blur_window(ctx, "Test", pos2(20., 20.));
+    blur_window(ctx, "Test 2", pos2(235., 40.));
+    // over both of the others, blurring them as they're composited
+    blur_window(ctx, "Test 3", pos2(130., 90.));
+
+    egui_blur::mark_layers(ctx);
+}
+
+fn menu_bar(ctx: &egui::Context, popup: &BlurPopup) {
+    BlurPanel::new(TopBottomPanel::top("menu_bar")).show(ctx, |ui| {
+        menu::bar(ui, |ui| {
+            popup.menu_button(ui, "Windows", reset_button);
+
+            let mut dark_mode = ui.visuals().dark_mode;
+            let combo_box = ComboBox::from_id_source("theme").selected_text(if dark_mode {
+                "Dark"
+            } else {
+                "Light"
+            });
+            let response = popup.combo_box(ui, combo_box, |ui| {
+                ui.selectable_value(&mut dark_mode, true, "Dark");
+                ui.selectable_value(&mut dark_mode, false, "Light");
+            });
+            popup.on_hover_ui(response.response, |ui| {
+                ui.label("Theme of the windows and popups");
+            });
+
+            if dark_mode != ui.visuals().dark_mode {
+                ctx.set_visuals(if dark_mode {
+                    Visuals::dark()
+                } else {
+                    Visuals::light()
+                });
+            }
+        });
+    });
+}
+
+fn reset_button(ui: &mut Ui) {
+    if ui.button("Reset positions").clicked() {
+        ui.ctx().memory_mut(|memory| memory.reset_areas());
+        ui.close_menu();
+    }
+}
+
+/// Amounts of the CSS filter functions, applied after the blur in this
+/// order, and of the grain on top.
+#[derive(Clone, Copy)]
+struct Filters {
+    saturate: f32,
+    brightness: f32,
+    contrast: f32,
+    grayscale: f32,
+    sepia: f32,
+    hue_rotate: f32,
+    invert: f32,
+    noise: f32,
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self {
+            saturate: 1.0,
+            brightness: 1.0,
+            contrast: 1.0,
+            grayscale: 0.0,
+            sepia: 0.0,
+            hue_rotate: 0.0,
+            invert: 0.0,
+            noise: 0.0,
+        }
+    }
+}
+
+/// The window's material, the filters which are left at their defaults are
+/// skipped by the library.
+fn backdrop(params: &BlurParams, filters: &Filters) -> Vec<BackdropFilter> {
+    use BackdropFilter::*;
+
+    let blur = match params.mode {
+        BlurMode::Gaussian => Blur(params.radius),
+        BlurMode::DualKawase => KawaseBlur(params.radius),
+    };
+
+    vec![
+        blur,
+        Saturate(filters.saturate),
+        Brightness(filters.brightness),
+        Contrast(filters.contrast),
+        Grayscale(filters.grayscale),
+        Sepia(filters.sepia),
+        HueRotate(filters.hue_rotate),
+        Invert(filters.invert),
+        Tint(params.tint, params.tint_strength),
+        Noise(filters.noise),
+        Opacity(params.opacity),
+    ]
+}
+
+fn blur_window(ctx: &egui::Context, title: &str, default_pos: Pos2) {
+    let id = Id::new(title);
+    let (mut params, mut filters) =
+        ctx.data_mut(|d| *d.get_temp_mut_or_default::<(BlurParams, Filters)>(id));
+
+    BlurWindow::new(title)
+        .blur(backdrop(&params, &filters))
+        .resizable(true)
+        .default_pos(default_pos)
+        .default_size(vec2(200., 260.))
+        .show(ctx, |ui| {
+            blur_params_ui(ui, &mut params);
+            filters_ui(ui, &mut filters);
+            ui.allocate_space(ui.available_size());
+        });
+
+    ctx.data_mut(|d| d.insert_temp(id, (params, filters)));
+}
+
+fn blur_params_ui(ui: &mut Ui, params: &mut BlurParams) {
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut params.mode, BlurMode::Gaussian, "Gaussian");
+        ui.radio_value(&mut params.mode, BlurMode::DualKawase, "Dual Kawase");
+    });
+
+    ui.add(Slider::new(&mut params.radius, 1.0..=96.0).text("radius"));
+
+    ui.horizontal(|ui| {
+        ui.color_edit_button_srgba(&mut params.tint);
+        ui.add(Slider::new(&mut params.tint_strength, 0.0..=1.0).text("tint"));
+    });
+
+    ui.add(Slider::new(&mut params.opacity, 0.0..=1.0).text("opacity"));
+}
+
+fn filters_ui(ui: &mut Ui, filters: &mut Filters) {
+    CollapsingHeader::new("Filters").show(ui, |ui| {
+        ui.add(Slider::new(&mut filters.saturate, 0.0..=3.0).text("saturate"));
+        ui.add(Slider::new(&mut filters.brightness, 0.0..=2.0).text("brightness"));
+        ui.add(Slider::new(&mut filters.contrast, 0.0..=2.0).text("contrast"));
+        ui.add(Slider::new(&mut filters.grayscale, 0.0..=1.0).text("grayscale"));
+        ui.add(Slider::new(&mut filters.sepia, 0.0..=1.0).text("sepia"));
+        ui.add(Slider::new(&mut filters.hue_rotate, 0.0..=360.0).text("hue rotate"));
+        ui.add(Slider::new(&mut filters.invert, 0.0..=1.0).text("invert"));
+        ui.add(Slider::new(&mut filters.noise, 0.0..=0.2).text("noise"));
+
+        if ui.button("Vibrancy").clicked() {
+            *filters = Filters {
+                saturate: 1.8,
+                noise: 0.03,
+                ..Default::default()
+            };
+        }
+    });
+}
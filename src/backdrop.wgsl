@@ -0,0 +1,68 @@
+// The colour stage: the colour matrix, then the tint, then the grain.
+@fragment
+fn fs_color(
+    in: VertexOut
+) -> @location(0) vec4<f32> {
+    var rgb = mix(apply_color_matrix(load(in.clip_position.xy)), region.tint.rgb, region.tint.a);
+
+    if region.noise > 0.0 {
+        let grain = (hash(vec2<u32>(in.clip_position.xy)) - 0.5) * region.noise;
+        let gamma = clamp(gamma_from_linear(rgb) + grain, vec3<f32>(0.0), vec3<f32>(1.0));
+
+        rgb = linear_from_gamma(gamma);
+    }
+
+    return vec4<f32>(rgb, 1.0);
+}
+
+// Composites the last stage's output onto the window texture, cut to the
+// region's rounded rect.
+@fragment
+fn fs_composite(
+    in: VertexOut
+) -> @location(0) vec4<f32> {
+    let pos = mix(region.rect.xy, region.rect.zw, in.tex_coords);
+
+    return vec4<f32>(load(in.clip_position.xy), region.opacity * coverage(pos));
+}
+
+struct MaskOut {
+    @location(0) coverage: f32,
+    @builtin(position) clip_position: vec4<f32>,
+}
+
+// Places a vertex of the region's mask, as egui's tessellator made it.
+@vertex
+fn vs_mask(
+    @location(0) pos: vec2<f32>,
+    @location(1) color: vec4<f32>,
+) -> MaskOut {
+    var out: MaskOut;
+    let p = pos / screen;
+
+    out.clip_position = vec4<f32>(p.x * 2.0 - 1.0, 1.0 - p.y * 2.0, 0.0, 1.0);
+    // egui feathers the edges by fading the alpha out
+    out.coverage = color.a;
+
+    return out;
+}
+
+// Copies the last stage's output within the region's mask, with the mask's
+// coverage as the alpha.
+@fragment
+fn fs_mask(
+    in: MaskOut
+) -> @location(0) vec4<f32> {
+    return vec4<f32>(load(in.clip_position.xy), in.coverage);
+}
+
+// Composites the output of `fs_mask` onto the window texture, cut to the
+// coverage it drew.
+@fragment
+fn fs_composite_mask(
+    in: VertexOut
+) -> @location(0) vec4<f32> {
+    let covered = textureLoad(t_diffuse, vec2<i32>(in.clip_position.xy), 0);
+
+    return vec4<f32>(covered.rgb, region.opacity * covered.a);
+}
@@ -0,0 +1,219 @@
+use egui::Color32;
+
+use crate::{
+    color_matrix::ColorMatrix,
+    custom_shader::CustomShader,
+    pipeline::{BlurMode, BlurParams},
+};
+
+/// One step of a region's backdrop, the counterpart of a CSS
+/// `backdrop-filter` function. A region applies a list of them in order to
+/// what is painted behind it, which lets every window declare its material:
+///
+/// ```ignore
+/// use BackdropFilter::*;
+///
+/// BlurWindow::new("Settings")
+///     .blur([Blur(12.0), Saturate(1.8), Tint(Color32::WHITE, 0.2), Noise(0.03)])
+///     .show(ctx, |ui| ui.label("frosted"));
+/// ```
+///
+/// Filters which leave the backdrop unchanged are skipped, and consecutive
+/// colour filters share a single pass. One after a filter which can take
+/// colours out of range, such as `Saturate(1.8)`, gets a pass of its own, as
+/// CSS clamps them in between.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BackdropFilter {
+    /// Gaussian blur reaching this far, in points, at three standard
+    /// deviations.
+    Blur(f32),
+    /// Dual kawase blur reaching about this far, in points. Its cost barely
+    /// grows with the radius, which makes it the choice for large radii.
+    KawaseBlur(f32),
+    ColorMatrix(ColorMatrix),
+    /// See [`ColorMatrix::saturate`].
+    Saturate(f32),
+    /// See [`ColorMatrix::brightness`].
+    Brightness(f32),
+    /// See [`ColorMatrix::contrast`].
+    Contrast(f32),
+    /// See [`ColorMatrix::grayscale`].
+    Grayscale(f32),
+    /// See [`ColorMatrix::sepia`].
+    Sepia(f32),
+    /// See [`ColorMatrix::hue_rotate`].
+    HueRotate(f32),
+    /// See [`ColorMatrix::invert`].
+    Invert(f32),
+    /// Mixes in a colour, its alpha ignored, by an amount from `0.0` to
+    /// `1.0`.
+    Tint(Color32, f32),
+    /// Grain of up to this amplitude in gamma encoded colour, which hides
+    /// the banding of smooth gradients.
+    Noise(f32),
+    /// Opacity of the filtered backdrop over the unfiltered one, wherever it
+    /// is in the list.
+    Opacity(f32),
+    /// A shader registered with
+    /// [`BlurShaders::register_custom`](crate::BlurShaders::register_custom),
+    /// with the contents of its uniform block.
+    Custom(CustomShader, [f32; 16]),
+}
+
+impl BackdropFilter {
+    /// The colour transform of the filters which are one.
+    fn color_matrix(self) -> Option<ColorMatrix> {
+        Some(match self {
+            Self::ColorMatrix(matrix) => matrix,
+            Self::Saturate(amount) => ColorMatrix::saturate(amount),
+            Self::Brightness(amount) => ColorMatrix::brightness(amount),
+            Self::Contrast(amount) => ColorMatrix::contrast(amount),
+            Self::Grayscale(amount) => ColorMatrix::grayscale(amount),
+            Self::Sepia(amount) => ColorMatrix::sepia(amount),
+            Self::HueRotate(degrees) => ColorMatrix::hue_rotate(degrees),
+            Self::Invert(amount) => ColorMatrix::invert(amount),
+            _ => return None,
+        })
+    }
+}
+
+impl From<BlurParams> for Vec<BackdropFilter> {
+    fn from(params: BlurParams) -> Self {
+        let blur = match params.mode {
+            BlurMode::Gaussian => BackdropFilter::Blur(params.radius),
+            BlurMode::DualKawase => BackdropFilter::KawaseBlur(params.radius),
+        };
+
+        vec![
+            blur,
+            BackdropFilter::ColorMatrix(params.color_matrix),
+            BackdropFilter::Tint(params.tint, params.tint_strength),
+            BackdropFilter::Opacity(params.opacity),
+        ]
+    }
+}
+
+/// A list of filters compiled into the stages rendering it.
+pub(crate) struct CompiledBackdrop {
+    pub stages: Vec<Stage>,
+    pub opacity: f32,
+}
+
+/// A step of a region's backdrop, each of which reads the output of the one
+/// before.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Stage {
+    Blur {
+        mode: BlurMode,
+        radius: f32,
+    },
+    Color(ColorStage),
+    Custom {
+        shader: CustomShader,
+        params: [f32; 16],
+    },
+}
+
+/// The colour filters merged into a single pass, which applies them in this
+/// order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ColorStage {
+    pub color_matrix: ColorMatrix,
+    pub tint: Color32,
+    pub tint_strength: f32,
+    pub noise: f32,
+}
+
+impl Default for ColorStage {
+    fn default() -> Self {
+        Self {
+            color_matrix: ColorMatrix::IDENTITY,
+            tint: Color32::WHITE,
+            tint_strength: 0.0,
+            noise: 0.0,
+        }
+    }
+}
+
+impl Stage {
+    /// How far around a pixel the stage reads its input, in points.
+    pub fn reach(&self) -> f32 {
+        match *self {
+            // see `vs_blur_horizontal` and `vs_chain`
+            Self::Blur {
+                mode: BlurMode::Gaussian,
+                radius,
+            } => radius.ceil() + 1.0,
+            Self::Blur {
+                mode: BlurMode::DualKawase,
+                radius,
+            } => 2.0 * radius,
+            Self::Color(_) => 0.0,
+            Self::Custom { shader, .. } => shader.reach,
+        }
+    }
+}
+
+/// The colour stage at the end of `stages` if it `fits`, otherwise a new one.
+fn color_stage(stages: &mut Vec<Stage>, fits: impl Fn(&ColorStage) -> bool) -> &mut ColorStage {
+    if !matches!(stages.last(), Some(Stage::Color(stage)) if fits(stage)) {
+        stages.push(Stage::Color(ColorStage::default()));
+    }
+
+    match stages.last_mut() {
+        Some(Stage::Color(stage)) => stage,
+        _ => unreachable!(),
+    }
+}
+
+/// Compiles `filters` into stages, leaving out the filters which do nothing
+/// and merging the colour filters which can share a pass.
+pub(crate) fn compile(filters: &[BackdropFilter]) -> CompiledBackdrop {
+    let mut stages = Vec::new();
+    let mut opacity = 1.0;
+
+    for &filter in filters {
+        match filter {
+            BackdropFilter::Blur(radius) if radius > 0.0 => stages.push(Stage::Blur {
+                mode: BlurMode::Gaussian,
+                radius,
+            }),
+            BackdropFilter::KawaseBlur(radius) if radius > 0.0 => stages.push(Stage::Blur {
+                mode: BlurMode::DualKawase,
+                radius,
+            }),
+            BackdropFilter::Tint(tint, strength) if strength > 0.0 => {
+                // the tint goes after the stage's colour matrix
+                let stage = color_stage(&mut stages, |stage| {
+                    stage.tint_strength == 0.0 && stage.noise == 0.0
+                });
+
+                stage.tint = tint;
+                stage.tint_strength = strength.min(1.0);
+            }
+            BackdropFilter::Noise(amount) if amount > 0.0 => {
+                color_stage(&mut stages, |stage| stage.noise == 0.0).noise = amount;
+            }
+            BackdropFilter::Opacity(amount) => opacity *= amount.clamp(0.0, 1.0),
+            BackdropFilter::Custom(shader, params) => stages.push(Stage::Custom { shader, params }),
+            filter => match filter.color_matrix() {
+                Some(matrix) if !matrix.is_identity() => {
+                    // CSS clamps the colours after every function, which
+                    // only leaves those of the stage unchanged if its matrix
+                    // keeps them in range
+                    let stage = color_stage(&mut stages, |stage| {
+                        stage.tint_strength == 0.0
+                            && stage.noise == 0.0
+                            && stage.color_matrix.stays_in_range()
+                    });
+
+                    stage.color_matrix = stage.color_matrix.then(matrix);
+                }
+                // blurs of no radius, tints and noise of no amount
+                _ => {}
+            },
+        }
+    }
+
+    CompiledBackdrop { stages, opacity }
+}
@@ -0,0 +1,79 @@
+use egui::{epaint::Shadow, Area, Color32, Context, Frame, InnerResponse, Ui};
+
+use crate::{
+    backdrop_filter::BackdropFilter,
+    blur_callback::{layer_blur_callback, BlurSlot},
+    pipeline::BlurParams,
+};
+
+/// An [`egui::Area`] with a frame drawn over a blurred copy of what is behind
+/// it, the layers below the area's as long as they're marked by
+/// [`mark_layers`].
+///
+/// ```ignore
+/// BlurArea::new(egui::Area::new("hud").anchor(Align2::RIGHT_TOP, vec2(-8.0, 8.0)))
+///     .show(ctx, |ui| ui.label("frosted"));
+/// ```
+///
+/// [`mark_layers`]: crate::mark_layers
+#[must_use = "You should call .show()"]
+pub struct BlurArea {
+    area: Area,
+    filters: Vec<BackdropFilter>,
+    frame: Option<Frame>,
+}
+
+impl BlurArea {
+    pub fn new(area: Area) -> Self {
+        Self {
+            area,
+            filters: BlurParams::default().into(),
+            frame: None,
+        }
+    }
+
+    /// The area's material, [`BlurParams`] or any list of
+    /// [`BackdropFilter`]s.
+    pub fn blur(mut self, filters: impl Into<Vec<BackdropFilter>>) -> Self {
+        self.filters = filters.into();
+        self
+    }
+
+    /// Frame painted over the blurred backdrop, which is cut to it. Defaults
+    /// to the style's popup frame without fill and shadow.
+    pub fn frame(mut self, frame: Frame) -> Self {
+        self.frame = Some(frame);
+        self
+    }
+
+    pub fn show<R>(
+        self,
+        ctx: &Context,
+        add_contents: impl FnOnce(&mut Ui) -> R,
+    ) -> InnerResponse<R> {
+        let Self {
+            area,
+            filters,
+            frame,
+        } = self;
+
+        let layer = area.layer();
+        let slot = BlurSlot::reserve(ctx.layer_painter(layer));
+
+        let frame = frame.unwrap_or_else(|| {
+            Frame::popup(&ctx.style())
+                .fill(Color32::TRANSPARENT)
+                .shadow(Shadow::NONE)
+        });
+
+        let InnerResponse { inner, response } = area.show(ctx, |ui| frame.show(ui, add_contents));
+        slot.fill(layer_blur_callback(
+            layer,
+            inner.response.rect,
+            frame.rounding,
+            filters,
+        ));
+
+        InnerResponse::new(inner.inner, response)
+    }
+}
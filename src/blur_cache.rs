@@ -0,0 +1,267 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    mem,
+};
+
+use egui::{
+    epaint::{Mesh, Primitive},
+    ClippedPrimitive, Rect, Rounding,
+};
+
+use crate::{
+    backdrop_filter::BackdropFilter,
+    blur_callback::{BlurCallback, ScissorRect},
+};
+
+/// The filtered backdrops of the last frame's blur callbacks, each in a
+/// texture of its own, so their stages only run again once what is painted
+/// under them changes.
+#[derive(Default)]
+pub(crate) struct BlurCache {
+    entries: Vec<CacheEntry>,
+    /// entries of the frame before, until claimed by [`Self::claim`]
+    previous: Vec<CacheEntry>,
+}
+
+/// What a region's filtered backdrop depends on, besides the backdrop.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CacheKey {
+    pub rect: Rect,
+    pub clip_rect: Rect,
+    pub rounding: Rounding,
+    pub filters: Vec<BackdropFilter>,
+    pub pixels_per_point: f32,
+}
+
+struct CacheEntry {
+    key: CacheKey,
+    /// hash of the backdrop the texture holds the filtered version of, `None`
+    /// before it was filtered or if the backdrop can't be hashed
+    backdrop: Option<u64>,
+    /// as large as the region's scissor rect
+    texture: wgpu::Texture,
+}
+
+/// Slot of a region in the cache.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CacheSlot {
+    pub index: usize,
+    /// The texture holds the filtered backdrop, the stages can be skipped.
+    pub hit: bool,
+}
+
+impl BlurCache {
+    /// Starts a frame, the entries which aren't claimed before the next one
+    /// are dropped.
+    pub fn begin_frame(&mut self) {
+        self.previous = mem::take(&mut self.entries);
+    }
+
+    /// Drops every filtered backdrop, e.g. when textures egui paints with
+    /// changed.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.previous.clear();
+    }
+
+    /// Claims the entry of the region `key` for this frame, which hits if it
+    /// was filtered from the same `backdrop` before. Otherwise it's taken to
+    /// hold the filtered `backdrop` once the region is painted.
+    pub fn claim(
+        &mut self,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        key: CacheKey,
+        backdrop: Option<u64>,
+        scissor: &ScissorRect,
+    ) -> CacheSlot {
+        let size = wgpu::Extent3d {
+            width: scissor.width,
+            height: scissor.height,
+            depth_or_array_layers: 1,
+        };
+
+        let previous = self
+            .previous
+            .iter()
+            .position(|entry| entry.key == key && entry.texture.size() == size);
+
+        let (entry, hit) = match previous {
+            Some(index) => {
+                let mut entry = self.previous.swap_remove(index);
+                let hit = backdrop.is_some() && entry.backdrop == backdrop;
+                entry.backdrop = backdrop;
+
+                (entry, hit)
+            }
+            None => {
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("blur_cache"),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST,
+                    view_formats: &[],
+                });
+
+                let entry = CacheEntry {
+                    key,
+                    backdrop,
+                    texture,
+                };
+
+                (entry, false)
+            }
+        };
+
+        self.entries.push(entry);
+
+        CacheSlot {
+            index: self.entries.len() - 1,
+            hit,
+        }
+    }
+
+    pub fn texture(&self, index: usize) -> &wgpu::Texture {
+        &self.entries[index].texture
+    }
+}
+
+/// What a blur callback painted below a region adds to its backdrop, besides
+/// the callback itself.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RegionHash {
+    /// hash of its tessellated mask, see [`mask_hash`]
+    pub mask: Option<u64>,
+    /// hash of its own backdrop, over the area its stages read
+    pub backdrop: Option<u64>,
+}
+
+/// Hash of what `paint_jobs` paint within `area`, or `None` if they hold a
+/// callback of some other kind, whose output can't be told.
+///
+/// Meshes are hashed with their clip rects and texture ids, and only the
+/// triangles of them reaching into `area`, as egui merges the shapes of a
+/// layer into one mesh per clip rect. Blur callbacks are hashed with their
+/// rect, rounding, filters and what `regions` holds of them, by job. Their
+/// blur spreads what is painted around them into `area`, which their own
+/// backdrop hash, taken over all they read, stands in for.
+pub(crate) fn backdrop_hash(
+    paint_jobs: &[ClippedPrimitive],
+    regions: &HashMap<usize, RegionHash>,
+    area: Rect,
+) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+
+    for (
+        job,
+        ClippedPrimitive {
+            clip_rect,
+            primitive,
+        },
+    ) in paint_jobs.iter().enumerate()
+    {
+        let visible = clip_rect.intersect(area);
+
+        if !visible.is_positive() {
+            continue;
+        }
+
+        match primitive {
+            Primitive::Mesh(mesh) => {
+                if !mesh.calc_bounds().intersects(visible) {
+                    continue;
+                }
+
+                hash_rect(&mut hasher, *clip_rect);
+                mesh.texture_id.hash(&mut hasher);
+
+                for triangle in mesh.indices.chunks_exact(3) {
+                    let vertices = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+
+                    if Rect::from_points(&vertices.map(|vertex| vertex.pos)).intersects(visible) {
+                        bytemuck::cast_slice::<_, u8>(&vertices).hash(&mut hasher);
+                    }
+                }
+            }
+            Primitive::Callback(callback) => {
+                if !callback.rect.intersects(visible) {
+                    continue;
+                }
+
+                let blur = callback.callback.downcast_ref::<BlurCallback>()?;
+
+                hash_rect(&mut hasher, *clip_rect);
+                hash_rect(&mut hasher, blur.rect);
+                hash_rounding(&mut hasher, blur.rounding);
+                blur.filters.len().hash(&mut hasher);
+                for filter in &blur.filters {
+                    hash_filter(&mut hasher, filter);
+                }
+                // the regions left out paint nothing
+                if let Some(region) = regions.get(&job) {
+                    region.mask.hash(&mut hasher);
+                    region.backdrop?.hash(&mut hasher);
+                }
+            }
+        }
+    }
+
+    Some(hasher.finish())
+}
+
+/// Hash of a tessellated mask, for [`backdrop_hash`].
+pub(crate) fn mask_hash(mesh: &Mesh) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    bytemuck::cast_slice::<_, u8>(&mesh.vertices).hash(&mut hasher);
+    mesh.indices.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+fn hash_rect(hasher: &mut impl Hasher, rect: Rect) {
+    [rect.min.x, rect.min.y, rect.max.x, rect.max.y]
+        .map(f32::to_bits)
+        .hash(hasher);
+}
+
+fn hash_rounding(hasher: &mut impl Hasher, rounding: Rounding) {
+    [rounding.nw, rounding.ne, rounding.sw, rounding.se]
+        .map(f32::to_bits)
+        .hash(hasher);
+}
+
+fn hash_filter(hasher: &mut impl Hasher, filter: &BackdropFilter) {
+    mem::discriminant(filter).hash(hasher);
+
+    match *filter {
+        BackdropFilter::Blur(amount)
+        | BackdropFilter::KawaseBlur(amount)
+        | BackdropFilter::Saturate(amount)
+        | BackdropFilter::Brightness(amount)
+        | BackdropFilter::Contrast(amount)
+        | BackdropFilter::Grayscale(amount)
+        | BackdropFilter::Sepia(amount)
+        | BackdropFilter::HueRotate(amount)
+        | BackdropFilter::Invert(amount)
+        | BackdropFilter::Noise(amount)
+        | BackdropFilter::Opacity(amount) => amount.to_bits().hash(hasher),
+        BackdropFilter::ColorMatrix(matrix) => {
+            matrix.rows.map(|row| row.map(f32::to_bits)).hash(hasher)
+        }
+        BackdropFilter::Tint(color, amount) => {
+            color.hash(hasher);
+            amount.to_bits().hash(hasher);
+        }
+        BackdropFilter::Custom(shader, uniforms) => {
+            shader.shaders.hash(hasher);
+            shader.index.hash(hasher);
+            shader.reach.to_bits().hash(hasher);
+            uniforms.map(f32::to_bits).hash(hasher);
+        }
+    }
+}
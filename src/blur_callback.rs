@@ -0,0 +1,736 @@
+use std::{ops::Range, sync::Arc};
+
+use egui::{
+    epaint::{Mesh, TessellationOptions, Tessellator},
+    layers::ShapeIdx,
+    LayerId, PaintCallback, Painter, Rect, Rounding, Shape,
+};
+use wgpu::RenderPassDescriptor;
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    backdrop_filter::{self, BackdropFilter, CompiledBackdrop, Stage},
+    blur_cache::CacheSlot,
+    pipeline::{kawase_iterations, BlurMode, Region, RegionUniforms, WindowPipelineRegistry},
+    window_texture::WindowTexture,
+};
+
+/// Paint callback filtering what was painted under `rect` before it, to be
+/// placed under the content that should appear on the filtered backdrop. The
+/// backdrop is cut to the rect with its corners rounded by `rounding`, as
+/// [`egui::Shape::Rect`] would be.
+///
+/// `filters` is a list of [`BackdropFilter`]s, or [`BlurParams`] for the
+/// usual blur.
+///
+/// It isn't run by egui-wgpu but by [`WindowTexture::render`], which ends
+/// egui's render pass at the callback to filter the window texture.
+///
+/// [`BlurParams`]: crate::BlurParams
+pub fn blur_callback(
+    rect: Rect,
+    rounding: Rounding,
+    filters: impl Into<Vec<BackdropFilter>>,
+) -> PaintCallback {
+    BlurCallback {
+        rect,
+        rounding,
+        filters: filters.into(),
+        layer: None,
+        mask: None,
+    }
+    .into()
+}
+
+/// Same as [`blur_callback`], for a region of `layer` to be painted on it.
+/// Its backdrop is what the layers below `layer` paint, no matter where on
+/// the layer the callback is, and it's composited under everything the layer
+/// paints. Other regions on the layer don't see it and it doesn't see them.
+///
+/// This needs the frame's layers marked by [`mark_layers`], and otherwise
+/// falls back to what [`blur_callback`] does.
+///
+/// [`mark_layers`]: crate::mark_layers
+pub fn layer_blur_callback(
+    layer: LayerId,
+    rect: Rect,
+    rounding: Rounding,
+    filters: impl Into<Vec<BackdropFilter>>,
+) -> PaintCallback {
+    BlurCallback {
+        rect,
+        rounding,
+        filters: filters.into(),
+        layer: Some(layer),
+        mask: None,
+    }
+    .into()
+}
+
+/// Same as [`blur_callback`], with the backdrop cut to `shape` instead of a
+/// rect, such as a circle, a [`egui::epaint::PathShape`] or a
+/// [`egui::Mesh`]. The shape is tessellated the way egui paints it, and its
+/// alpha, antialiased at the edges, is how much of the filtered backdrop
+/// shows, so it's usually filled with an opaque colour. Text is covered
+/// glyph by glyph, by rects.
+pub fn shape_blur_callback(
+    shape: impl Into<Shape>,
+    filters: impl Into<Vec<BackdropFilter>>,
+) -> PaintCallback {
+    let shape = shape.into();
+
+    BlurCallback {
+        rect: shape.visual_bounding_rect(),
+        rounding: Rounding::none(),
+        filters: filters.into(),
+        layer: None,
+        mask: Some(shape),
+    }
+    .into()
+}
+
+/// Same as [`shape_blur_callback`], for a region of `layer` as
+/// [`layer_blur_callback`] paints it.
+pub fn layer_shape_blur_callback(
+    layer: LayerId,
+    shape: impl Into<Shape>,
+    filters: impl Into<Vec<BackdropFilter>>,
+) -> PaintCallback {
+    let shape = shape.into();
+
+    BlurCallback {
+        rect: shape.visual_bounding_rect(),
+        rounding: Rounding::none(),
+        filters: filters.into(),
+        layer: Some(layer),
+        mask: Some(shape),
+    }
+    .into()
+}
+
+/// Placeholder for a blurred region under contents yet to be painted, swapped
+/// for its callback once their rect is known.
+pub(crate) struct BlurSlot {
+    painter: Painter,
+    idx: ShapeIdx,
+}
+
+impl BlurSlot {
+    /// Reserves the next shape of `painter`.
+    pub fn reserve(painter: Painter) -> Self {
+        let idx = painter.add(Shape::Noop);
+
+        Self { painter, idx }
+    }
+
+    /// Puts `callback` in place of the placeholder, unless its rect is empty.
+    pub fn fill(self, callback: PaintCallback) {
+        if callback.rect.is_positive() {
+            self.painter.set(self.idx, Shape::Callback(callback));
+        }
+    }
+}
+
+pub(crate) struct BlurCallback {
+    /// The rect of the region, the bounding rect of `mask` if it has one.
+    pub rect: Rect,
+    pub rounding: Rounding,
+    pub filters: Vec<BackdropFilter>,
+    /// the layer whose bottom the region is painted at, if any
+    pub layer: Option<LayerId>,
+    /// shape the backdrop is cut to in place of the rounded rect
+    pub mask: Option<Shape>,
+}
+
+impl From<BlurCallback> for PaintCallback {
+    fn from(blur: BlurCallback) -> Self {
+        Self {
+            rect: blur.rect,
+            callback: Arc::new(blur),
+        }
+    }
+}
+
+impl BlurCallback {
+    /// Tessellates the mask, the part of it within `clip_rect`, if the
+    /// region has one.
+    pub fn tessellate_mask(&self, clip_rect: Rect, pixels_per_point: f32) -> Option<Mesh> {
+        let shape = self.mask.clone()?;
+
+        // no font texture, text isn't drawn with it anyway
+        let mut tessellator = Tessellator::new(
+            pixels_per_point,
+            TessellationOptions::default(),
+            [1, 1],
+            vec![],
+        );
+        tessellator.set_clip_rect(clip_rect);
+
+        let mut mesh = Mesh::default();
+        tessellator.tessellate_shape(shape, &mut mesh);
+
+        Some(mesh)
+    }
+
+    /// Compiles the filters and claims a uniform slot for every pass, along
+    /// with the area the first stage reads. Returns `None` if they leave the
+    /// backdrop as it is or nothing of the rect is left within `clip_rect`.
+    pub fn prepare(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        regions: &mut RegionUniforms,
+        clip_rect: Rect,
+        pixels_per_point: f32,
+    ) -> Option<(Vec<PreparedStage>, u32, Rect)> {
+        let CompiledBackdrop { stages, opacity } = backdrop_filter::compile(&self.filters);
+        let visible = self.rect.intersect(clip_rect);
+
+        if stages.is_empty() || opacity == 0.0 || !visible.is_positive() {
+            return None;
+        }
+
+        let region = Region::new(self.rect, self.rounding, pixels_per_point);
+
+        // only the visible part of the rect is composited, every stage has to
+        // fill as much around it as the stages after it read
+        let mut reach = 0.0;
+        let mut areas: Vec<Rect> = stages
+            .iter()
+            .rev()
+            .map(|stage| {
+                let area = visible.expand(reach);
+                reach += stage.reach();
+                area
+            })
+            .collect();
+        areas.reverse();
+
+        let backdrop = visible.expand(reach);
+
+        let prepared = stages
+            .iter()
+            .zip(areas)
+            .map(|(stage, area)| {
+                let mut region = Region { area, ..region };
+
+                let pass = match *stage {
+                    Stage::Blur { mode, radius } => {
+                        region.radius = radius;
+
+                        match mode {
+                            BlurMode::Gaussian => StagePass::Gaussian,
+                            BlurMode::DualKawase => StagePass::DualKawase {
+                                iterations: kawase_iterations(radius * pixels_per_point),
+                            },
+                        }
+                    }
+                    Stage::Color(color) => {
+                        region.color_matrix = color.color_matrix;
+                        region.tint = color.tint;
+                        region.tint_strength = color.tint_strength;
+                        region.noise = color.noise;
+
+                        StagePass::Color
+                    }
+                    Stage::Custom { shader, params } => {
+                        region.custom = params;
+
+                        StagePass::Custom {
+                            index: shader.index,
+                        }
+                    }
+                };
+
+                PreparedStage {
+                    pass,
+                    offset: regions.push(device, queue, encoder, &region),
+                }
+            })
+            .collect();
+
+        let composite = regions.push(device, queue, encoder, &Region { opacity, ..region });
+
+        Some((prepared, composite, backdrop))
+    }
+}
+
+/// A blur callback found by [`WindowTexture::prepare`].
+pub(crate) struct PreparedBlur {
+    /// Index of the paint job the region is painted before, the callback's
+    /// own or the first of its layer's.
+    pub job: usize,
+    /// The rect cut to the clip rect of the callback's paint job.
+    pub scissor: ScissorRect,
+    pub stages: Vec<PreparedStage>,
+    /// Dynamic offset of the composite's uniform slot.
+    pub composite: u32,
+    pub cache: CacheSlot,
+    pub mask: Option<PreparedMask>,
+}
+
+/// Where the tessellated mask of a region is in
+/// [`MaskBuffers`](crate::pipeline::MaskBuffers), which its composite is cut
+/// to rather than the rounded rect.
+pub(crate) struct PreparedMask {
+    pub base_vertex: i32,
+    pub indices: Range<u32>,
+}
+
+pub(crate) struct PreparedStage {
+    pub pass: StagePass,
+    /// Dynamic offset of the stage's uniform slot.
+    pub offset: u32,
+}
+
+pub(crate) enum StagePass {
+    Gaussian,
+    DualKawase { iterations: usize },
+    Color,
+    Custom { index: usize },
+}
+
+/// Texture a stage reads its input from.
+#[derive(Clone, Copy)]
+enum Source {
+    Window,
+    PingPong(usize),
+}
+
+impl Source {
+    /// The ping-pong texture a pass reading `self` draws to, which is what
+    /// the next pass reads.
+    fn next(self) -> usize {
+        match self {
+            Self::Window => 0,
+            Self::PingPong(i) => 1 - i,
+        }
+    }
+
+    fn bind_group(self, registry: &WindowPipelineRegistry) -> &wgpu::BindGroup {
+        match self {
+            Self::Window => &registry.window_bind_group,
+            Self::PingPong(i) => &registry.ping_pong_bind_groups[i],
+        }
+    }
+}
+
+/// Part of the framebuffer a blur is drawn to, in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScissorRect {
+    /// `rect` cut to `clip_rect` and the framebuffer of `size` pixels, the
+    /// way egui-wgpu clips meshes, or `None` if nothing of it is left.
+    pub fn new(
+        rect: Rect,
+        clip_rect: Rect,
+        pixels_per_point: f32,
+        size: PhysicalSize<u32>,
+    ) -> Option<Self> {
+        let PhysicalSize { width, height } = size;
+
+        // casting saturates, which takes care of anything left of or above
+        // the framebuffer
+        let to_pixels = |points: f32| (points * pixels_per_point).round() as u32;
+        let visible = rect.intersect(clip_rect);
+
+        let min_x = to_pixels(visible.min.x).min(width);
+        let min_y = to_pixels(visible.min.y).min(height);
+        let max_x = to_pixels(visible.max.x).clamp(min_x, width);
+        let max_y = to_pixels(visible.max.y).clamp(min_y, height);
+
+        (max_x > min_x && max_y > min_y).then_some(Self {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x,
+            height: max_y - min_y,
+        })
+    }
+}
+
+/// Paints `blurs`, regions painted before the same paint job and so on the
+/// same backdrop. The stages of each run over the window texture, from one of
+/// the ping-pong textures to the other, and only once all of them are done
+/// are their outputs composited back onto it, so none of them sees another.
+///
+/// The last stage's output is kept in the region's cache texture, which
+/// stands in for the stages as long as the backdrop stays the same. Every
+/// pass only covers the visible part of the rect and what the passes after
+/// it read around that, so the cost follows the blurred area rather than the
+/// window's size. The stages still read the backdrop around the clip rect.
+pub(crate) fn paint(
+    encoder: &mut wgpu::CommandEncoder,
+    wt: &WindowTexture,
+    blurs: &[PreparedBlur],
+) {
+    // the region whose output the ping-pong textures still hold
+    let mut filtered = None;
+
+    for (i, blur) in blurs.iter().enumerate() {
+        if !blur.cache.hit {
+            filtered = Some((i, filter(encoder, wt, blur)));
+        }
+    }
+
+    for (i, blur) in blurs.iter().enumerate() {
+        let source = match filtered {
+            Some((filtered, source)) if filtered == i => source,
+            _ => {
+                let cache = wt.blur_cache().texture(blur.cache.index);
+                copy_scissor(
+                    encoder,
+                    cache,
+                    wt.ping_pong_texture(0),
+                    &blur.scissor,
+                    false,
+                );
+                filtered = Some((i, Source::PingPong(0)));
+
+                Source::PingPong(0)
+            }
+        };
+
+        paint_composite(encoder, wt, source, blur);
+    }
+}
+
+/// Runs the stages of `blur` and keeps their output in its cache texture,
+/// returning where else it is.
+fn filter(encoder: &mut wgpu::CommandEncoder, wt: &WindowTexture, blur: &PreparedBlur) -> Source {
+    let source = blur
+        .stages
+        .iter()
+        .fold(Source::Window, |source, stage| match stage.pass {
+            StagePass::Gaussian => paint_gaussian(encoder, wt, source, stage.offset),
+            StagePass::DualKawase { iterations } => {
+                paint_dual_kawase(encoder, wt, source, stage.offset, iterations)
+            }
+            StagePass::Color => paint_color(encoder, wt, source, stage.offset),
+            StagePass::Custom { index } => paint_custom(encoder, wt, source, stage.offset, index),
+        });
+
+    let output = match source {
+        Source::Window => wt.texture(),
+        Source::PingPong(i) => wt.ping_pong_texture(i),
+    };
+    let cache = wt.blur_cache().texture(blur.cache.index);
+    copy_scissor(encoder, output, cache, &blur.scissor, true);
+
+    source
+}
+
+/// Copies what's within `scissor` of a texture as large as the window to a
+/// cache texture as large as `scissor`, or back if `to_cache` is false.
+fn copy_scissor(
+    encoder: &mut wgpu::CommandEncoder,
+    from: &wgpu::Texture,
+    to: &wgpu::Texture,
+    scissor: &ScissorRect,
+    to_cache: bool,
+) {
+    let frame_origin = wgpu::Origin3d {
+        x: scissor.x,
+        y: scissor.y,
+        z: 0,
+    };
+    let (from_origin, to_origin) = match to_cache {
+        true => (frame_origin, wgpu::Origin3d::ZERO),
+        false => (wgpu::Origin3d::ZERO, frame_origin),
+    };
+
+    encoder.copy_texture_to_texture(
+        wgpu::ImageCopyTexture {
+            texture: from,
+            mip_level: 0,
+            origin: from_origin,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyTexture {
+            texture: to,
+            mip_level: 0,
+            origin: to_origin,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::Extent3d {
+            width: scissor.width,
+            height: scissor.height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+/// Draws the region at `offset` into `view` in a pass of its own, limited to
+/// `scissor` if given. The shaders place their quads themselves, so the
+/// default viewport spanning the whole target is kept.
+fn draw_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+    pipeline: &wgpu::RenderPipeline,
+    bind_group: &wgpu::BindGroup,
+    regions: &RegionUniforms,
+    offset: u32,
+    scissor: Option<&ScissorRect>,
+) {
+    let mut render_pass = begin_pass(
+        encoder, view, pipeline, bind_group, regions, offset, scissor,
+    );
+    render_pass.draw(0..4, 0..1);
+}
+
+/// Begins the pass of [`draw_pass`], set up for anything to be drawn with
+/// `pipeline`.
+fn begin_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    view: &'a wgpu::TextureView,
+    pipeline: &'a wgpu::RenderPipeline,
+    bind_group: &'a wgpu::BindGroup,
+    regions: &'a RegionUniforms,
+    offset: u32,
+    scissor: Option<&ScissorRect>,
+) -> wgpu::RenderPass<'a> {
+    let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: true,
+            },
+        })],
+        depth_stencil_attachment: None,
+    });
+
+    if let Some(scissor) = scissor {
+        render_pass.set_scissor_rect(scissor.x, scissor.y, scissor.width, scissor.height);
+    }
+
+    render_pass.set_pipeline(pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    // the region and the custom uniforms share a slot
+    render_pass.set_bind_group(1, &regions.bind_group, &[offset, offset]);
+
+    render_pass
+}
+
+/// Blurs `source` with the stage at `offset`, in a horizontal pass into one
+/// ping-pong texture and a vertical pass back into the other.
+fn paint_gaussian(
+    encoder: &mut wgpu::CommandEncoder,
+    wt: &WindowTexture,
+    source: Source,
+    offset: u32,
+) -> Source {
+    let registry = wt.pipeline_registry();
+    let horizontal = source.next();
+    let vertical = Source::PingPong(horizontal).next();
+
+    draw_pass(
+        encoder,
+        wt.ping_pong_view(horizontal),
+        &registry.blur_horizontal_pipeline,
+        source.bind_group(registry),
+        &registry.regions,
+        offset,
+        None,
+    );
+
+    draw_pass(
+        encoder,
+        wt.ping_pong_view(vertical),
+        &registry.blur_vertical_pipeline,
+        &registry.ping_pong_bind_groups[horizontal],
+        &registry.regions,
+        offset,
+        None,
+    );
+
+    Source::PingPong(vertical)
+}
+
+/// Blurs `source` with the stage at `offset` into a ping-pong texture, by
+/// downsampling `iterations` levels deep and upsampling back.
+fn paint_dual_kawase(
+    encoder: &mut wgpu::CommandEncoder,
+    wt: &WindowTexture,
+    source: Source,
+    offset: u32,
+    iterations: usize,
+) -> Source {
+    let registry = wt.pipeline_registry();
+    let WindowPipelineRegistry {
+        kawase_downsample_pipeline,
+        kawase_upsample_pipeline,
+        kawase_upsample_final_pipeline,
+        kawase_bind_groups,
+        regions,
+        ..
+    } = registry;
+
+    for level in 0..iterations {
+        // level `0` is downsampled from the source
+        let bind_group = match level {
+            0 => source.bind_group(registry),
+            _ => &kawase_bind_groups[level - 1],
+        };
+
+        draw_pass(
+            encoder,
+            wt.kawase_view(level),
+            kawase_downsample_pipeline,
+            bind_group,
+            regions,
+            offset,
+            None,
+        );
+    }
+
+    for level in (1..iterations).rev() {
+        draw_pass(
+            encoder,
+            wt.kawase_view(level - 1),
+            kawase_upsample_pipeline,
+            &kawase_bind_groups[level],
+            regions,
+            offset,
+            None,
+        );
+    }
+
+    let target = source.next();
+
+    draw_pass(
+        encoder,
+        wt.ping_pong_view(target),
+        kawase_upsample_final_pipeline,
+        &kawase_bind_groups[0],
+        regions,
+        offset,
+        None,
+    );
+
+    Source::PingPong(target)
+}
+
+/// Applies the colour stage at `offset` to `source`, into a ping-pong
+/// texture.
+fn paint_color(
+    encoder: &mut wgpu::CommandEncoder,
+    wt: &WindowTexture,
+    source: Source,
+    offset: u32,
+) -> Source {
+    let registry = wt.pipeline_registry();
+    let target = source.next();
+
+    draw_pass(
+        encoder,
+        wt.ping_pong_view(target),
+        &registry.color_pipeline,
+        source.bind_group(registry),
+        &registry.regions,
+        offset,
+        None,
+    );
+
+    Source::PingPong(target)
+}
+
+/// Runs the custom shader `index` of the stage at `offset` over `source`,
+/// into a ping-pong texture. [`WindowTexture::prepare`] leaves out the
+/// regions with shaders it has no pipeline for.
+fn paint_custom(
+    encoder: &mut wgpu::CommandEncoder,
+    wt: &WindowTexture,
+    source: Source,
+    offset: u32,
+    index: usize,
+) -> Source {
+    let registry = wt.pipeline_registry();
+    let pipeline = &registry.custom_pipelines[index];
+    let target = source.next();
+
+    draw_pass(
+        encoder,
+        wt.ping_pong_view(target),
+        pipeline,
+        source.bind_group(registry),
+        &registry.regions,
+        offset,
+        None,
+    );
+
+    Source::PingPong(target)
+}
+
+/// Blends the output of the last stage of `blur`, `source`, onto the window
+/// texture within its scissor rect, through its mask if it has one.
+fn paint_composite(
+    encoder: &mut wgpu::CommandEncoder,
+    wt: &WindowTexture,
+    source: Source,
+    blur: &PreparedBlur,
+) {
+    let registry = wt.pipeline_registry();
+
+    let Some(mask) = &blur.mask else {
+        draw_pass(
+            encoder,
+            wt.view(),
+            &registry.composite_pipeline,
+            source.bind_group(registry),
+            &registry.regions,
+            blur.composite,
+            Some(&blur.scissor),
+        );
+        return;
+    };
+
+    // the mask's coverage goes to the alpha of the ping-pong texture the
+    // output isn't in, the triangles overlapping keep the larger
+    let Source::PingPong(output) = source else {
+        unreachable!("regions have at least one stage");
+    };
+    let covered = Source::PingPong(1 - output);
+
+    {
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("blur_mask"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: wt.ping_pong_view(1 - output),
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        let scissor = &blur.scissor;
+        render_pass.set_scissor_rect(scissor.x, scissor.y, scissor.width, scissor.height);
+        render_pass.set_pipeline(&registry.mask_pipeline);
+        render_pass.set_bind_group(0, source.bind_group(registry), &[]);
+        render_pass.set_bind_group(1, &registry.regions.bind_group, &[blur.composite; 2]);
+        render_pass.set_vertex_buffer(0, registry.masks.vertices.slice(..));
+        render_pass.set_index_buffer(registry.masks.indices.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(mask.indices.clone(), mask.base_vertex, 0..1);
+    }
+
+    draw_pass(
+        encoder,
+        wt.view(),
+        &registry.composite_mask_pipeline,
+        covered.bind_group(registry),
+        &registry.regions,
+        blur.composite,
+        Some(&blur.scissor),
+    );
+}
@@ -0,0 +1,100 @@
+use egui::{
+    Color32, Context, Frame, InnerResponse, LayerId, Rounding, SidePanel, TopBottomPanel, Ui,
+};
+
+use crate::{
+    backdrop_filter::BackdropFilter,
+    blur_callback::{blur_callback, BlurSlot},
+    pipeline::BlurParams,
+};
+
+/// An [`egui::SidePanel`] or [`egui::TopBottomPanel`] drawn over a blurred
+/// copy of what was painted under it.
+///
+/// Panels are painted on the background layer, so unlike windows they don't
+/// blur other layers but what the app painted on the background before
+/// showing them, such as an image spanning the screen.
+///
+/// ```ignore
+/// BlurPanel::new(egui::SidePanel::left("tools").resizable(true))
+///     .show(ctx, |ui| ui.label("frosted"));
+/// ```
+#[must_use = "You should call .show()"]
+pub struct BlurPanel {
+    panel: Panel,
+    filters: Vec<BackdropFilter>,
+    frame: Option<Frame>,
+}
+
+/// The kinds of panels egui has which can be blurred.
+pub enum Panel {
+    Side(SidePanel),
+    TopBottom(TopBottomPanel),
+}
+
+impl From<SidePanel> for Panel {
+    fn from(panel: SidePanel) -> Self {
+        Self::Side(panel)
+    }
+}
+
+impl From<TopBottomPanel> for Panel {
+    fn from(panel: TopBottomPanel) -> Self {
+        Self::TopBottom(panel)
+    }
+}
+
+impl BlurPanel {
+    pub fn new(panel: impl Into<Panel>) -> Self {
+        Self {
+            panel: panel.into(),
+            filters: BlurParams::default().into(),
+            frame: None,
+        }
+    }
+
+    /// The panel's material, [`BlurParams`] or any list of
+    /// [`BackdropFilter`]s.
+    pub fn blur(mut self, filters: impl Into<Vec<BackdropFilter>>) -> Self {
+        self.filters = filters.into();
+        self
+    }
+
+    /// Frame painted over the blurred backdrop. Defaults to the style's
+    /// panel frame without fill.
+    pub fn frame(mut self, frame: Frame) -> Self {
+        self.frame = Some(frame);
+        self
+    }
+
+    pub fn show<R>(
+        self,
+        ctx: &Context,
+        add_contents: impl FnOnce(&mut Ui) -> R,
+    ) -> InnerResponse<R> {
+        let Self {
+            panel,
+            filters,
+            frame,
+        } = self;
+
+        // egui paints panels on the background, under what is painted there
+        // after them
+        let slot = BlurSlot::reserve(ctx.layer_painter(LayerId::background()));
+
+        let frame =
+            frame.unwrap_or_else(|| Frame::side_top_panel(&ctx.style()).fill(Color32::TRANSPARENT));
+
+        let response = match panel {
+            Panel::Side(panel) => panel.frame(frame).show(ctx, add_contents),
+            Panel::TopBottom(panel) => panel.frame(frame).show(ctx, add_contents),
+        };
+        slot.fill(blur_callback(
+            response.response.rect,
+            Rounding::none(),
+            filters,
+        ));
+
+        response
+    }
+}
@@ -0,0 +1,138 @@
+use egui::{ComboBox, Context, Id, InnerResponse, Pos2, Response, Ui, WidgetText};
+
+use crate::{backdrop_filter::BackdropFilter, blur_style::blur_frame, pipeline::BlurParams};
+
+/// Popups, combo box dropdowns, menus and tooltips drawn over a blurred copy
+/// of what is behind them, the layers below theirs as long as they're marked
+/// by [`mark_layers`]. Each method shows the egui container of the same name
+/// and sets the frame egui paints around it to the material with
+/// [`blur_frame`], so the app has to run [`blur_frames`] on its shapes.
+///
+/// Submenus opened from a menu's contents are blurred if they're shown with
+/// [`Self::menu_button`] too, menus are closed with [`Ui::close_menu`] as
+/// usual.
+///
+/// ```ignore
+/// let popup = BlurPopup::new();
+/// popup.menu_button(ui, "File", |ui| {
+///     popup.menu_button(ui, "Recent", |ui| ui.label("nothing yet"));
+/// });
+/// popup.on_hover_ui(ui.button("Save"), |ui| { ui.label("frosted"); });
+/// ```
+///
+/// [`mark_layers`]: crate::mark_layers
+/// [`blur_frames`]: crate::blur_frames
+#[derive(Clone)]
+pub struct BlurPopup {
+    filters: Vec<BackdropFilter>,
+}
+
+impl Default for BlurPopup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlurPopup {
+    pub fn new() -> Self {
+        Self {
+            filters: BlurParams::default().into(),
+        }
+    }
+
+    /// The popups' material, [`BlurParams`] or any list of
+    /// [`BackdropFilter`]s.
+    pub fn blur(mut self, filters: impl Into<Vec<BackdropFilter>>) -> Self {
+        self.filters = filters.into();
+        self
+    }
+
+    /// Same as [`egui::popup_below_widget`].
+    pub fn below_widget<R>(
+        &self,
+        ui: &Ui,
+        popup_id: Id,
+        widget_response: &Response,
+        add_contents: impl FnOnce(&mut Ui) -> R,
+    ) -> Option<R> {
+        egui::popup_below_widget(ui, popup_id, widget_response, |ui| {
+            self.blur_frame(ui);
+            add_contents(ui)
+        })
+    }
+
+    /// Same as [`egui::ComboBox::show_ui`].
+    pub fn combo_box<R>(
+        &self,
+        ui: &mut Ui,
+        combo_box: ComboBox,
+        menu_contents: impl FnOnce(&mut Ui) -> R,
+    ) -> InnerResponse<Option<R>> {
+        combo_box.show_ui(ui, |ui| {
+            self.blur_frame(ui);
+            menu_contents(ui)
+        })
+    }
+
+    /// Same as [`egui::Ui::menu_button`], which opens a submenu when shown
+    /// from the contents of another menu.
+    pub fn menu_button<R>(
+        &self,
+        ui: &mut Ui,
+        title: impl Into<WidgetText>,
+        add_contents: impl FnOnce(&mut Ui) -> R,
+    ) -> InnerResponse<Option<R>> {
+        ui.menu_button(title, |ui| {
+            self.blur_frame(ui);
+            add_contents(ui)
+        })
+    }
+
+    /// Same as [`egui::Response::context_menu`].
+    pub fn context_menu(&self, response: Response, add_contents: impl FnOnce(&mut Ui)) -> Response {
+        response.context_menu(|ui| {
+            self.blur_frame(ui);
+            add_contents(ui)
+        })
+    }
+
+    /// Same as [`egui::show_tooltip`].
+    pub fn tooltip<R>(
+        &self,
+        ctx: &Context,
+        id: Id,
+        add_contents: impl FnOnce(&mut Ui) -> R,
+    ) -> Option<R> {
+        egui::show_tooltip(ctx, id, |ui| {
+            self.blur_frame(ui);
+            add_contents(ui)
+        })
+    }
+
+    /// Same as [`egui::show_tooltip_at`].
+    pub fn tooltip_at<R>(
+        &self,
+        ctx: &Context,
+        id: Id,
+        suggested_position: Option<Pos2>,
+        add_contents: impl FnOnce(&mut Ui) -> R,
+    ) -> Option<R> {
+        egui::show_tooltip_at(ctx, id, suggested_position, |ui| {
+            self.blur_frame(ui);
+            add_contents(ui)
+        })
+    }
+
+    /// Same as [`egui::Response::on_hover_ui`].
+    pub fn on_hover_ui(&self, response: Response, add_contents: impl FnOnce(&mut Ui)) -> Response {
+        response.on_hover_ui(|ui| {
+            self.blur_frame(ui);
+            add_contents(ui)
+        })
+    }
+
+    /// Sets the frame of the popup `ui` is the contents of to the material.
+    fn blur_frame(&self, ui: &Ui) {
+        blur_frame(ui.ctx(), ui.layer_id(), self.filters.clone());
+    }
+}
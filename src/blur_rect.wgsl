@@ -10,83 +10,90 @@ struct VertexOut {
     @builtin(position) clip_position: vec4<f32>,
 }
 
-@vertex
-fn vs_main(
-    @builtin(vertex_index) v_idx: u32
-) -> VertexOut {
+@group(0) @binding(0)
+var<uniform> screen: vec2<f32>;
+@group(0) @binding(1)
+var t_diffuse: texture_2d<f32>;
+@group(0) @binding(2)
+var s_diffuse: sampler;
+
+// covers `area` (in points) with the quad
+fn quad(area: vec4<f32>, v_idx: u32) -> VertexOut {
     var out: VertexOut;
     let vert = v_positions[v_idx];
 
-    out.clip_position = vec4<f32>(vert, 0.0, 1.0);
     out.tex_coords = vec2<f32>(
         (vert.x + 1.0) / 2.0,
         (-vert.y + 1.0) / 2.0,
     );
 
+    let pos = mix(area.xy, area.zw, out.tex_coords) / screen;
+    out.clip_position = vec4<f32>(pos.x * 2.0 - 1.0, 1.0 - pos.y * 2.0, 0.0, 1.0);
+
     return out;
 }
 
-@group(0) @binding(0)
-var<uniform> screen: vec2<f32>;
-@group(0) @binding(1)
-var<uniform> rect: vec4<f32>;
-@group(0) @binding(2)
-var t_diffuse: texture_2d<f32>;
-@group(0) @binding(3)
-var s_diffuse: sampler;
-
-var<private> pi: f32 = 3.141592653589793;
-
-@fragment
-fn fs_main(
-    in: VertexOut
-) -> @location(0) vec4<f32> {
-    let x = rect.x;
-    let y = rect.y;
-    let width = rect.z - x;
-    let height = rect.w - y;
+@vertex
+fn vs_blur_horizontal(
+    @builtin(vertex_index) v_idx: u32
+) -> VertexOut {
+    // the vertical pass reads up to the radius above and below the rect, so
+    // the horizontal pass has to fill that band as well
+    let extent = ceil(region.radius) + 1.0;
 
-    let coord_x = x + (width  * in.tex_coords.x);
-    let coord_y = y + (height * in.tex_coords.y);
+    return quad(region.area + vec4<f32>(0.0, -extent, 0.0, extent), v_idx);
+}
 
-    let sigma = 9.0;
-    let k = 2.0 * sigma * sigma;
+@vertex
+fn vs_blur_vertical(
+    @builtin(vertex_index) v_idx: u32
+) -> VertexOut {
+    return quad(region.area, v_idx);
+}
 
-    let size = i32(floor(sigma * 3.0));
+// One dimensional gaussian along `direction`, with taps spaced in physical
+// pixels. Neighbouring taps are merged into a single bilinear fetch placed
+// between them according to their weights, which halves the tap count.
+fn blur(frag_coord: vec2<f32>, direction: vec2<f32>) -> vec3<f32> {
+    let dims = vec2<f32>(textureDimensions(t_diffuse));
+    let uv = frag_coord / dims;
 
-    var rgba = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    // the radius spans 3 sigma, in points
+    let sigma_px = region.radius / 3.0 * dims.x / screen.x;
+    let k = max(2.0 * sigma_px * sigma_px, 1e-6);
+    let size = i32(ceil(sigma_px * 3.0));
 
-    for(var i: i32 = -size; i <= size; i++) {
-        for(var j: i32 = -size; j <= size; j++) {
-            let i_f32 = f32(i);
-            let j_f32 = f32(j);
+    var rgb = textureSampleLevel(t_diffuse, s_diffuse, uv, 0.0).rgb;
+    var total = 1.0;
 
-            let fac = exp(-(i_f32*i_f32 + j_f32*j_f32) / k) / (pi * k);
+    for (var i: i32 = 1; i <= size; i += 2) {
+        let i_f32 = f32(i);
 
-            let pos = vec2<f32>(
-                (coord_x + f32(i)) / screen.x,
-                (coord_y + f32(j)) / screen.y
-            );
+        let w0 = exp(-(i_f32 * i_f32) / k);
+        let w1 = exp(-((i_f32 + 1.0) * (i_f32 + 1.0)) / k);
+        let w = w0 + w1;
 
-            let sampled = textureSample(
-                t_diffuse, s_diffuse,
-                pos
-            );
+        let offset = (i_f32 * w0 + (i_f32 + 1.0) * w1) / w;
+        let delta = direction * offset / dims;
 
-            rgba += vec4<f32>(
-                sampled.x * fac,
-                sampled.y * fac,
-                sampled.z * fac,
-                0.0
-            );
-        }
+        rgb += w * textureSampleLevel(t_diffuse, s_diffuse, uv + delta, 0.0).rgb;
+        rgb += w * textureSampleLevel(t_diffuse, s_diffuse, uv - delta, 0.0).rgb;
+        total += 2.0 * w;
     }
 
-    rgba = mix(
-        rgba,
-        vec4<f32>(0.0, 0.5, 0.6, 1.0),
-        0.0
-    );
+    return rgb / total;
+}
+
+@fragment
+fn fs_blur_horizontal(
+    in: VertexOut
+) -> @location(0) vec4<f32> {
+    return vec4<f32>(blur(in.clip_position.xy, vec2<f32>(1.0, 0.0)), 1.0);
+}
 
-    return rgba;
-}
\ No newline at end of file
+@fragment
+fn fs_blur_vertical(
+    in: VertexOut
+) -> @location(0) vec4<f32> {
+    return vec4<f32>(blur(in.clip_position.xy, vec2<f32>(0.0, 1.0)), 1.0);
+}
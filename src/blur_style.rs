@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use egui::{
+    epaint::{ClippedShape, Mesh, RectShape},
+    Color32, Context, Id, LayerId, Order, Rect, Rounding, Shape,
+};
+
+use crate::{
+    backdrop_filter::BackdropFilter, blur_callback::layer_blur_callback, layers::LayerEnd,
+    pipeline::BlurParams,
+};
+
+/// Materials of the frames egui paints around windows, popups, menus and
+/// tooltips, for [`blur_frames`] to put them over a blurred backdrop. Kinds
+/// of frames left at `None` are painted as egui paints them, as are the
+/// frames of layers [`blur_frame`] sets to `None`.
+///
+/// The default blurs all of them with the default [`BlurParams`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlurStyle {
+    /// Frames of [`egui::Window`]s.
+    pub window: Option<Vec<BackdropFilter>>,
+    /// Frames of [`egui::popup_below_widget`], which combo boxes and color
+    /// pickers use too.
+    pub popup: Option<Vec<BackdropFilter>>,
+    /// Frames of menus, context menus and their submenus.
+    pub menu: Option<Vec<BackdropFilter>>,
+    /// Frames of [`egui::show_tooltip`] and hover texts.
+    pub tooltip: Option<Vec<BackdropFilter>>,
+}
+
+impl Default for BlurStyle {
+    fn default() -> Self {
+        let filters = Some(BlurParams::default().into());
+
+        Self {
+            window: filters.clone(),
+            popup: filters.clone(),
+            menu: filters.clone(),
+            tooltip: filters,
+        }
+    }
+}
+
+impl BlurStyle {
+    /// Material of the frames on `layer`, by the kind of area egui shows on
+    /// layers of its order. Areas of the app's own are taken for windows,
+    /// the order they are shown in by default.
+    fn filters(&self, ctx: &Context, layer: LayerId) -> Option<&[BackdropFilter]> {
+        let filters = match layer.order {
+            Order::Middle => &self.window,
+            // popups are the areas of the popup egui has open, menus keep
+            // track of being open themselves
+            Order::Foreground if ctx.memory(|memory| memory.is_popup_open(layer.id)) => &self.popup,
+            Order::Foreground => &self.menu,
+            Order::Tooltip => &self.tooltip,
+            _ => return None,
+        };
+
+        filters.as_deref()
+    }
+}
+
+fn style_id() -> Id {
+    Id::new("egui_blur::BlurStyle")
+}
+
+/// Materials of the layers set with [`blur_frame`] this frame.
+fn layers_id() -> Id {
+    Id::new("egui_blur::blur_frame")
+}
+
+/// Blurs the backdrops of the frames egui paints with `style` from now on,
+/// for every app sharing `ctx`. `None` paints them as egui does again.
+pub fn set_blur_style(ctx: &Context, style: impl Into<Option<BlurStyle>>) {
+    ctx.data_mut(|data| match style.into() {
+        Some(style) => data.insert_temp(style_id(), style),
+        None => data.remove::<BlurStyle>(style_id()),
+    });
+}
+
+/// The style set with [`set_blur_style`], if any.
+pub fn blur_style(ctx: &Context) -> Option<BlurStyle> {
+    ctx.data(|data| data.get_temp(style_id()))
+}
+
+/// Has [`blur_frames`] put the frame egui paints on `layer` over a backdrop
+/// of `filters` this frame, in place of the material of the [`BlurStyle`],
+/// or leave it as egui paints it with `None`, e.g. from within a window:
+///
+/// ```ignore
+/// egui::Window::new("opaque").show(ctx, |ui| {
+///     egui_blur::blur_frame(ui.ctx(), ui.layer_id(), None);
+/// });
+/// ```
+///
+/// The frame is blurred with `filters` even without a style.
+pub fn blur_frame(ctx: &Context, layer: LayerId, filters: impl Into<Option<Vec<BackdropFilter>>>) {
+    let filters = filters.into();
+
+    ctx.data_mut(|data| {
+        data.get_temp_mut_or_default::<HashMap<LayerId, Option<Vec<BackdropFilter>>>>(layers_id())
+            .insert(layer, filters)
+    });
+}
+
+/// Puts the frames egui paints around windows, popups, menus and tooltips
+/// over a blurred copy of the layers below theirs, with the material
+/// [`set_blur_style`] set for their kind, or [`blur_frame`] for their layer.
+/// Call it on the shapes of [`egui::FullOutput`] before tessellating them:
+///
+/// ```ignore
+/// let mut full_output = ctx.run(raw_input, |ctx| {
+///     ui(ctx);
+///     egui_blur::mark_layers(ctx);
+/// });
+/// egui_blur::blur_frames(&ctx, &mut full_output.shapes);
+/// let paint_jobs = ctx.tessellate(full_output.shapes);
+/// ```
+///
+/// A frame is the first shape painted on its layer, as marked by
+/// [`mark_layers`], if that is a rect enclosing the rest of the layer. Its
+/// fill is left out, give the material a tint for a colored glass, and its
+/// shadow only shows around it.
+///
+/// [`mark_layers`]: crate::mark_layers
+pub fn blur_frames(ctx: &Context, shapes: &mut Vec<ClippedShape>) {
+    let layers = ctx.data_mut(|data| {
+        std::mem::take(
+            data.get_temp_mut_or_default::<HashMap<LayerId, Option<Vec<BackdropFilter>>>>(
+                layers_id(),
+            ),
+        )
+    });
+    let blur_style = blur_style(ctx);
+    if blur_style.is_none() && layers.is_empty() {
+        return;
+    }
+
+    let mut regions = Vec::new();
+    let mut start = 0;
+
+    for end in 0..shapes.len() {
+        let ClippedShape(_, Shape::Callback(callback)) = &shapes[end] else {
+            continue;
+        };
+        let Some(&LayerEnd(layer)) = callback.callback.downcast_ref::<LayerEnd>() else {
+            continue;
+        };
+        let layer_start = std::mem::replace(&mut start, end + 1);
+
+        let set = layers.get(&layer);
+        let filters = match set {
+            Some(filters) => filters.as_deref(),
+            None => blur_style
+                .as_ref()
+                .and_then(|blur_style| blur_style.filters(ctx, layer)),
+        };
+        let Some(filters) = filters else {
+            continue;
+        };
+        let Some(frame) = (layer_start..end).find(|&i| !matches!(shapes[i].1, Shape::Noop)) else {
+            continue;
+        };
+
+        // the layers are set for their frame, the others could start with
+        // e.g. the first of the buttons of an area without one
+        let Some(rect) = frame_rect(&shapes[frame].1) else {
+            continue;
+        };
+        if set.is_none() && !encloses(rect, &shapes[frame + 1..end]) {
+            continue;
+        }
+
+        if let Some((rect, rounding)) = see_through(&mut shapes[frame].1) {
+            let clip_rect = shapes[frame].0;
+            let callback = layer_blur_callback(layer, rect, rounding, filters.to_vec());
+            regions.push((frame, ClippedShape(clip_rect, Shape::Callback(callback))));
+        }
+    }
+
+    // from the last, which keeps the positions of the others
+    for (frame, region) in regions.into_iter().rev() {
+        shapes.insert(frame, region);
+    }
+}
+
+/// Whether the visible part of every one of `shapes` is within `frame`, as
+/// the contents of egui's frames are.
+fn encloses(frame: Rect, shapes: &[ClippedShape]) -> bool {
+    // strokes along its edges, such as the line under a window's title, reach
+    // a little past it
+    let frame = frame.expand(2.0);
+
+    shapes.iter().all(|ClippedShape(clip_rect, shape)| {
+        let visible = shape.visual_bounding_rect().intersect(*clip_rect);
+        !visible.is_positive() || frame.contains_rect(visible)
+    })
+}
+
+/// The rect of `shape` if it's a frame, see [`see_through`].
+fn frame_rect(shape: &Shape) -> Option<Rect> {
+    // frames with a shadow paint it first
+    let frame = match shape {
+        Shape::Vec(shapes) => match shapes.as_slice() {
+            [Shape::Mesh(_), frame] => frame,
+            _ => return None,
+        },
+        frame => frame,
+    };
+
+    match frame {
+        Shape::Rect(RectShape { rect, .. }) => Some(*rect),
+        _ => None,
+    }
+}
+
+/// Leaves the fill out of `shape` if it's a frame, along with the part of
+/// its shadow under it, returning its rect and rounding.
+fn see_through(shape: &mut Shape) -> Option<(Rect, Rounding)> {
+    // frames with a shadow paint it first
+    let (shadow, frame) = match shape {
+        Shape::Vec(shapes) => match shapes.as_mut_slice() {
+            [Shape::Mesh(shadow), frame] => (Some(shadow), frame),
+            _ => return None,
+        },
+        frame => (None, frame),
+    };
+
+    let Shape::Rect(RectShape {
+        rect,
+        rounding,
+        fill,
+        ..
+    }) = frame
+    else {
+        return None;
+    };
+    *fill = Color32::TRANSPARENT;
+
+    if let Some(shadow) = shadow {
+        cut_out(shadow);
+    }
+
+    Some((*rect, *rounding))
+}
+
+/// Drops the triangles of a shadow egui tessellated which are under its
+/// frame, those it fills with the full shadow color, keeping the ones
+/// fading out around the frame.
+fn cut_out(shadow: &mut Mesh) {
+    let vertices = &shadow.vertices;
+    let indices = std::mem::take(&mut shadow.indices);
+
+    shadow.indices = indices
+        .chunks_exact(3)
+        .filter(|triangle| {
+            triangle
+                .iter()
+                .any(|&i| vertices[i as usize].color == Color32::TRANSPARENT)
+        })
+        .flatten()
+        .copied()
+        .collect();
+}
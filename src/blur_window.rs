@@ -0,0 +1,137 @@
+use egui::{
+    epaint::Shadow, Align2, Color32, Context, Frame, Id, InnerResponse, LayerId, Order, Pos2, Ui,
+    Vec2, WidgetText, Window,
+};
+
+use crate::{
+    backdrop_filter::BackdropFilter,
+    blur_callback::{layer_blur_callback, BlurSlot},
+    pipeline::BlurParams,
+};
+
+/// An [`egui::Window`] drawn over a blurred copy of what is behind it, the
+/// layers below its own as long as they're marked by [`mark_layers`].
+///
+/// ```ignore
+/// BlurWindow::new("Settings")
+///     .blur(BlurParams { radius: 48.0, ..Default::default() })
+///     .show(ctx, |ui| ui.label("frosted"));
+/// ```
+///
+/// [`mark_layers`]: crate::mark_layers
+#[must_use = "You should call .show()"]
+pub struct BlurWindow<'open> {
+    window: Window<'open>,
+    id: Id,
+    filters: Vec<BackdropFilter>,
+    frame: Option<Frame>,
+}
+
+impl<'open> BlurWindow<'open> {
+    pub fn new(title: impl Into<WidgetText>) -> Self {
+        let title = title.into();
+        let id = Id::new(title.text());
+
+        Self {
+            window: Window::new(title),
+            id,
+            filters: BlurParams::default().into(),
+            frame: None,
+        }
+    }
+
+    /// Same as [`egui::Window::id`], the blur is painted on the layer with
+    /// this id.
+    pub fn id(mut self, id: Id) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// The window's material, [`BlurParams`] or any list of
+    /// [`BackdropFilter`]s.
+    pub fn blur(mut self, filters: impl Into<Vec<BackdropFilter>>) -> Self {
+        self.filters = filters.into();
+        self
+    }
+
+    /// Frame painted over the blurred backdrop. Defaults to the style's
+    /// window frame without fill and shadow, a translucent fill can be used
+    /// to shade the backdrop further.
+    pub fn frame(mut self, frame: Frame) -> Self {
+        self.frame = Some(frame);
+        self
+    }
+
+    pub fn open(mut self, open: &'open mut bool) -> Self {
+        self.window = self.window.open(open);
+        self
+    }
+
+    pub fn default_pos(mut self, default_pos: impl Into<Pos2>) -> Self {
+        self.window = self.window.default_pos(default_pos);
+        self
+    }
+
+    pub fn default_size(mut self, default_size: impl Into<Vec2>) -> Self {
+        self.window = self.window.default_size(default_size);
+        self
+    }
+
+    pub fn anchor(mut self, align: Align2, offset: impl Into<Vec2>) -> Self {
+        self.window = self.window.anchor(align, offset);
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.window = self.window.resizable(resizable);
+        self
+    }
+
+    pub fn collapsible(mut self, collapsible: bool) -> Self {
+        self.window = self.window.collapsible(collapsible);
+        self
+    }
+
+    pub fn title_bar(mut self, title_bar: bool) -> Self {
+        self.window = self.window.title_bar(title_bar);
+        self
+    }
+
+    /// Applies any other [`egui::Window`] option.
+    pub fn window(mut self, f: impl FnOnce(Window<'open>) -> Window<'open>) -> Self {
+        self.window = f(self.window);
+        self
+    }
+
+    pub fn show<R>(
+        self,
+        ctx: &Context,
+        add_contents: impl FnOnce(&mut Ui) -> R,
+    ) -> Option<InnerResponse<Option<R>>> {
+        let Self {
+            window,
+            id,
+            filters,
+            frame,
+        } = self;
+
+        // the window paints into the layer with its id, the placeholder at
+        // the bottom of that layer is swapped for the blur once the window's
+        // rect is known, which keeps it there should the layers be unmarked
+        let layer = LayerId::new(Order::Middle, id);
+        let slot = BlurSlot::reserve(ctx.layer_painter(layer));
+
+        let frame = frame.unwrap_or_else(|| {
+            Frame::window(&ctx.style())
+                .fill(Color32::TRANSPARENT)
+                .shadow(Shadow::NONE)
+        });
+
+        let rounding = frame.rounding;
+        let response = window.id(id).frame(frame).show(ctx, add_contents)?;
+        let rect = response.response.rect;
+        slot.fill(layer_blur_callback(layer, rect, rounding, filters));
+
+        Some(response)
+    }
+}
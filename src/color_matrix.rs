@@ -0,0 +1,192 @@
+/// Affine transform of the blurred backdrop's colours, the counterpart of the
+/// CSS `backdrop-filter` colour functions. Like browsers do, it applies to
+/// gamma encoded colours.
+///
+/// ```ignore
+/// // the vibrancy of macOS materials
+/// let params = BlurParams {
+///     color_matrix: ColorMatrix::saturate(1.8),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorMatrix {
+    /// Rows producing red, green and blue, each the weights of red, green
+    /// and blue followed by an offset.
+    pub rows: [[f32; 4]; 3],
+}
+
+impl Default for ColorMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl ColorMatrix {
+    pub const IDENTITY: Self = Self {
+        rows: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+        ],
+    };
+
+    /// Whether the matrix leaves colours unchanged, up to rounding errors of
+    /// the constructors.
+    pub fn is_identity(&self) -> bool {
+        self.rows
+            .iter()
+            .flatten()
+            .zip(Self::IDENTITY.rows.iter().flatten())
+            .all(|(a, b)| (a - b).abs() < 1e-5)
+    }
+
+    /// Whether the matrix maps every colour within `[0, 1]` to one within
+    /// it, which clamping its output then leaves as it is.
+    pub fn stays_in_range(&self) -> bool {
+        self.rows.iter().all(|&[r, g, b, offset]| {
+            let weights = [r, g, b];
+            let low: f32 = weights.iter().map(|w| w.min(0.0)).sum();
+            let high: f32 = weights.iter().map(|w| w.max(0.0)).sum();
+
+            offset + low >= -1e-5 && offset + high <= 1.0 + 1e-5
+        })
+    }
+
+    /// Applies `self`, then `next`. Unlike a list of CSS functions, the
+    /// colours aren't clamped to `[0, 1]` in between, which only makes a
+    /// difference if `self` isn't [`Self::stays_in_range`].
+    pub fn then(self, next: Self) -> Self {
+        let mut rows = [[0.0; 4]; 3];
+
+        for (row, next_row) in rows.iter_mut().zip(next.rows) {
+            for (col, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| next_row[k] * self.rows[k][col]).sum();
+            }
+
+            row[3] += next_row[3];
+        }
+
+        Self { rows }
+    }
+
+    /// Scales every channel by `amount` and adds `offset`.
+    fn linear(amount: f32, offset: f32) -> Self {
+        Self {
+            rows: [
+                [amount, 0.0, 0.0, offset],
+                [0.0, amount, 0.0, offset],
+                [0.0, 0.0, amount, offset],
+            ],
+        }
+    }
+
+    /// `saturate()`, `0.0` is fully desaturated, `1.0` leaves the colours
+    /// unchanged and anything above oversaturates them.
+    pub fn saturate(amount: f32) -> Self {
+        let s = amount;
+
+        Self {
+            rows: [
+                [0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s, 0.0],
+                [0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s, 0.0],
+                [0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s, 0.0],
+            ],
+        }
+    }
+
+    /// `brightness()`, scales the colours by `amount`.
+    pub fn brightness(amount: f32) -> Self {
+        Self::linear(amount, 0.0)
+    }
+
+    /// `contrast()`, scales the colours' distance from mid grey by `amount`.
+    pub fn contrast(amount: f32) -> Self {
+        Self::linear(amount, 0.5 - 0.5 * amount)
+    }
+
+    /// `grayscale()`, from `0.0` for the colours unchanged to `1.0` for fully
+    /// grey.
+    pub fn grayscale(amount: f32) -> Self {
+        let g = 1.0 - amount.clamp(0.0, 1.0);
+
+        Self {
+            rows: [
+                [
+                    0.2126 + 0.7874 * g,
+                    0.7152 - 0.7152 * g,
+                    0.0722 - 0.0722 * g,
+                    0.0,
+                ],
+                [
+                    0.2126 - 0.2126 * g,
+                    0.7152 + 0.2848 * g,
+                    0.0722 - 0.0722 * g,
+                    0.0,
+                ],
+                [
+                    0.2126 - 0.2126 * g,
+                    0.7152 - 0.7152 * g,
+                    0.0722 + 0.9278 * g,
+                    0.0,
+                ],
+            ],
+        }
+    }
+
+    /// `sepia()`, from `0.0` for the colours unchanged to `1.0` for fully
+    /// sepia.
+    pub fn sepia(amount: f32) -> Self {
+        let s = 1.0 - amount.clamp(0.0, 1.0);
+
+        Self {
+            rows: [
+                [0.393 + 0.607 * s, 0.769 - 0.769 * s, 0.189 - 0.189 * s, 0.0],
+                [0.349 - 0.349 * s, 0.686 + 0.314 * s, 0.168 - 0.168 * s, 0.0],
+                [0.272 - 0.272 * s, 0.534 - 0.534 * s, 0.131 + 0.869 * s, 0.0],
+            ],
+        }
+    }
+
+    /// `hue-rotate()`, by `degrees` around the colour wheel.
+    pub fn hue_rotate(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+
+        Self {
+            rows: [
+                [
+                    0.213 + cos * 0.787 - sin * 0.213,
+                    0.715 - cos * 0.715 - sin * 0.715,
+                    0.072 - cos * 0.072 + sin * 0.928,
+                    0.0,
+                ],
+                [
+                    0.213 - cos * 0.213 + sin * 0.143,
+                    0.715 + cos * 0.285 + sin * 0.140,
+                    0.072 - cos * 0.072 - sin * 0.283,
+                    0.0,
+                ],
+                [
+                    0.213 - cos * 0.213 - sin * 0.787,
+                    0.715 - cos * 0.715 + sin * 0.715,
+                    0.072 + cos * 0.928 + sin * 0.072,
+                    0.0,
+                ],
+            ],
+        }
+    }
+
+    /// `invert()`, from `0.0` for the colours unchanged to `1.0` for fully
+    /// inverted.
+    pub fn invert(amount: f32) -> Self {
+        let amount = amount.clamp(0.0, 1.0);
+
+        Self::linear(1.0 - 2.0 * amount, amount)
+    }
+
+    /// Transforms a gamma encoded colour.
+    pub fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        self.rows
+            .map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2] + row[3])
+    }
+}
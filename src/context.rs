@@ -1,30 +1,277 @@
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use naga::ShaderStage;
 use winit::dpi::PhysicalSize;
 
+use crate::{
+    custom_shader::{self, CustomShader, CustomShaderDescriptor},
+    pipeline::{
+        PipelineRegistry, FS_BLUR_HORIZONTAL, FS_BLUR_VERTICAL, FS_COLOR, FS_COMPOSITE,
+        FS_KAWASE_DOWNSAMPLE, FS_KAWASE_UPSAMPLE, FS_MAIN, VS_AREA, VS_BLUR_HORIZONTAL,
+        VS_BLUR_VERTICAL, VS_KAWASE_CHAIN, VS_KAWASE_FINAL, VS_MAIN, VS_RECT,
+    },
+    shader_source::{
+        self, ShaderError, ShaderSources, ShaderWatcher, COPY_BINDINGS, STAGE_BINDINGS,
+    },
+};
+
+/// A blur shader's complete source, with the region uniforms and colour
+/// helpers shared by all of them. WGSL doesn't mind the order of
+/// declarations, and putting `source` first keeps its line numbers in
+/// diagnostics.
+fn blur_source(sources: &ShaderSources, source: &str) -> String {
+    format!("{source}\n{}", sources.region)
+}
+
+/// `source` followed by everything the shaders of stages working pixel by
+/// pixel share.
+fn stage_source(sources: &ShaderSources, source: &str) -> String {
+    format!("{source}\n{}\n{}", sources.region, sources.stage)
+}
+
+fn create_module(device: &wgpu::Device, label: &str, source: String) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+    })
+}
+
+/// A built-in shader as compiled, along with what its pipelines need of it.
+struct BuiltIn {
+    label: &'static str,
+    source: String,
+    bindings: &'static [(u32, u32)],
+    entry_points: &'static [(ShaderStage, &'static str)],
+}
+
+impl BuiltIn {
+    /// `copy_texture`, `blur_rect`, `dual_kawase` and `backdrop` of
+    /// `sources`.
+    fn all(sources: &ShaderSources) -> [Self; 4] {
+        use ShaderStage::{Fragment, Vertex};
+
+        [
+            Self {
+                label: "copy_texture.wgsl",
+                source: sources.copy_texture.to_string(),
+                bindings: &COPY_BINDINGS,
+                entry_points: &[(Vertex, VS_MAIN), (Fragment, FS_MAIN)],
+            },
+            Self {
+                label: "blur_rect.wgsl",
+                source: blur_source(sources, &sources.blur_rect),
+                bindings: &STAGE_BINDINGS,
+                entry_points: &[
+                    (Vertex, VS_BLUR_HORIZONTAL),
+                    (Fragment, FS_BLUR_HORIZONTAL),
+                    (Vertex, VS_BLUR_VERTICAL),
+                    (Fragment, FS_BLUR_VERTICAL),
+                ],
+            },
+            Self {
+                label: "dual_kawase.wgsl",
+                source: blur_source(sources, &sources.dual_kawase),
+                bindings: &STAGE_BINDINGS,
+                entry_points: &[
+                    (Vertex, VS_KAWASE_CHAIN),
+                    (Vertex, VS_KAWASE_FINAL),
+                    (Fragment, FS_KAWASE_DOWNSAMPLE),
+                    (Fragment, FS_KAWASE_UPSAMPLE),
+                ],
+            },
+            Self {
+                label: "backdrop.wgsl",
+                source: stage_source(sources, &sources.backdrop),
+                bindings: &STAGE_BINDINGS,
+                entry_points: &[
+                    (Vertex, VS_AREA),
+                    (Vertex, VS_RECT),
+                    (Fragment, FS_COLOR),
+                    (Fragment, FS_COMPOSITE),
+                ],
+            },
+        ]
+    }
+
+    fn check(&self) -> Result<(), ShaderError> {
+        let module = shader_source::parse(self.label, &self.source)?;
+
+        for &(stage, name) in self.entry_points {
+            shader_source::require_entry_point(&module, stage, name)?;
+        }
+
+        shader_source::check_bindings(&module, self.bindings)
+    }
+}
+
+static NEXT_SHADERS_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Shader modules of the blur pipelines, created once per device.
+pub struct BlurShaders {
+    pub copy_texture: wgpu::ShaderModule,
+    pub blur_rect: wgpu::ShaderModule,
+    pub dual_kawase: wgpu::ShaderModule,
+    pub backdrop: wgpu::ShaderModule,
+    /// Registered with [`Self::register_custom`], in order.
+    pub custom: Vec<wgpu::ShaderModule>,
+
+    /// what the modules were compiled from, the custom shaders by label and
+    /// their own source, to compile them again on [`Self::reload`]
+    sources: ShaderSources,
+    custom_sources: Vec<(String, String)>,
+    /// tells the custom shaders registered with these apart from the others'
+    id: u64,
+}
+
+impl BlurShaders {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self::with_sources(device, ShaderSources::built_in())
+    }
+
+    /// Compiles `sources` rather than the shaders built in. They aren't
+    /// checked first, see [`Self::reload`] for that.
+    pub fn with_sources(device: &wgpu::Device, sources: ShaderSources) -> Self {
+        let [copy_texture, blur_rect, dual_kawase, backdrop] = BuiltIn::all(&sources)
+            .map(|built_in| create_module(device, built_in.label, built_in.source));
+
+        Self {
+            copy_texture,
+            blur_rect,
+            dual_kawase,
+            backdrop,
+            custom: Vec::new(),
+            sources,
+            custom_sources: Vec::new(),
+            id: NEXT_SHADERS_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Checks `sources` with naga and replaces the built-in shaders with
+    /// them, compiling the custom shaders again along with them. If any of
+    /// them fails, or wgpu rejects the pipelines created from them, the
+    /// shaders are left as they were.
+    ///
+    /// Pipelines keep the shaders they were created with,
+    /// [`WindowTexture::reload_pipelines`](crate::WindowTexture::reload_pipelines)
+    /// creates them again.
+    pub fn reload(
+        &mut self,
+        device: &wgpu::Device,
+        sources: ShaderSources,
+    ) -> Result<(), ShaderError> {
+        let built_ins = BuiltIn::all(&sources);
+
+        for built_in in &built_ins {
+            built_in.check()?;
+        }
+
+        let custom_sources: Vec<_> = self
+            .custom_sources
+            .iter()
+            .map(|(label, source)| {
+                let source = stage_source(&sources, source);
+                custom_shader::validate(label, &source)?;
+
+                Ok((label, source))
+            })
+            .collect::<Result<_, ShaderError>>()?;
+
+        let [copy_texture, blur_rect, dual_kawase, backdrop] =
+            built_ins.map(|built_in| create_module(device, built_in.label, built_in.source));
+        let reloaded = Self {
+            copy_texture,
+            blur_rect,
+            dual_kawase,
+            backdrop,
+            custom: custom_sources
+                .into_iter()
+                .map(|(label, source)| create_module(device, label, source))
+                .collect(),
+            sources,
+            custom_sources: self.custom_sources.clone(),
+            // the custom shaders registered keep running
+            id: self.id,
+        };
+
+        // naga passes some shaders wgpu only rejects along with the layouts,
+        // which the window textures would otherwise be created with
+        PipelineRegistry::check_pipelines(device, &reloaded)
+            .map_err(|err| ShaderError::Pipeline(err.to_string()))?;
+
+        *self = reloaded;
+
+        Ok(())
+    }
+
+    /// Checks and compiles a shader for
+    /// [`BackdropFilter::Custom`](crate::BackdropFilter::Custom). The
+    /// [`WindowTexture`](crate::WindowTexture)s created from these shaders
+    /// before it was registered only run it after
+    /// [`WindowTexture::reload_pipelines`](crate::WindowTexture::reload_pipelines).
+    pub fn register_custom(
+        &mut self,
+        device: &wgpu::Device,
+        descriptor: &CustomShaderDescriptor,
+    ) -> Result<CustomShader, ShaderError> {
+        let source = stage_source(&self.sources, descriptor.source);
+        custom_shader::validate(descriptor.label, &source)?;
+
+        self.custom
+            .push(create_module(device, descriptor.label, source));
+        self.custom_sources
+            .push((descriptor.label.to_owned(), descriptor.source.to_owned()));
+
+        Ok(CustomShader {
+            shaders: self.id,
+            index: self.custom.len() - 1,
+            reach: descriptor.reach,
+        })
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// A wgpu instance, device and the blur shaders, for apps which leave setting
+/// up wgpu to this crate. Apps owning their device only need [`BlurShaders`].
 pub struct RenderContext {
     pub instance: wgpu::Instance,
     pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
 
-    pub shader_copy_texture: wgpu::ShaderModule,
-    pub shader_blur_rect: wgpu::ShaderModule,
+    pub shaders: BlurShaders,
+
+    /// set by [`Self::watch_shaders`]
+    shader_watcher: Option<ShaderWatcher>,
 }
 
 impl RenderContext {
     pub async fn new() -> Self {
+        Self::with_adapter_options(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        })
+        .await
+        .unwrap()
+    }
+
+    /// Sets up the context on the adapter picked by `options`, or `None` if
+    /// there is no such adapter. With `force_fallback_adapter` this renders
+    /// on a software adapter, as the golden image tests do.
+    pub async fn with_adapter_options(options: &wgpu::RequestAdapterOptions<'_>) -> Option<Self> {
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
             dx12_shader_compiler: Default::default(),
         });
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
+        let adapter = instance.request_adapter(options).await?;
 
         let (device, queue) = adapter
             .request_device(
@@ -38,23 +285,52 @@ impl RenderContext {
             .await
             .unwrap();
 
-        let shader_copy_texture =
-            device.create_shader_module(wgpu::include_wgsl!("copy_texture.wgsl"));
+        let shaders = BlurShaders::new(&device);
 
-        let shader_blur_rect = device.create_shader_module(wgpu::include_wgsl!("blur_rect.wgsl"));
-
-        Self {
+        Some(Self {
             instance,
             adapter,
             device,
             queue,
 
-            shader_copy_texture,
-            shader_blur_rect,
+            shaders,
+
+            shader_watcher: None,
+        })
+    }
+
+    /// Dev mode, which loads the built-in shaders from `dir`, usually
+    /// [`SHADER_SOURCE_DIR`](crate::SHADER_SOURCE_DIR), on the next
+    /// [`Self::reload_shaders`] and again whenever one of them changes.
+    pub fn watch_shaders(&mut self, dir: impl Into<PathBuf>) {
+        self.shader_watcher = Some(ShaderWatcher::new(dir.into()));
+    }
+
+    /// Reloads the shaders watched in dev mode if they changed since the
+    /// last call, to be called once per frame, and returns whether they
+    /// were. The window textures then pick them up with
+    /// [`WindowTexture::reload_pipelines`](crate::WindowTexture::reload_pipelines).
+    ///
+    /// A shader failing to compile is returned with naga's diagnostics, or
+    /// wgpu's error if the pipelines can't be created from it, and the
+    /// shaders before stay in use until it's fixed.
+    pub fn reload_shaders(&mut self) -> Result<bool, ShaderError> {
+        let Some(watcher) = &mut self.shader_watcher else {
+            return Ok(false);
+        };
+
+        if !watcher.poll() {
+            return Ok(false);
         }
+
+        let sources = ShaderSources::load(watcher.dir())
+            .map_err(|err| ShaderError::Read(format!("{:?}: {err}", watcher.dir())))?;
+        self.shaders.reload(&self.device, sources)?;
+
+        Ok(true)
     }
 
-    pub(super) fn create_window_surface<
+    pub fn create_window_surface<
         W: raw_window_handle::HasRawWindowHandle + raw_window_handle::HasRawDisplayHandle,
     >(
         &self,
@@ -64,14 +340,13 @@ impl RenderContext {
     ) -> (wgpu::Surface, wgpu::SurfaceConfiguration) {
         let surface = unsafe { self.instance.create_surface(window) }.unwrap();
 
-        let surface_caps = surface.get_capabilities(&adapter);
+        let surface_caps = surface.get_capabilities(adapter);
 
         let texture_format = surface_caps
             .formats
             .iter()
             .copied()
-            .filter(|f| f.is_srgb())
-            .next()
+            .find(|f| f.is_srgb())
             .unwrap_or(surface_caps.formats[0]);
 
         let config = wgpu::SurfaceConfiguration {
@@ -87,11 +362,7 @@ impl RenderContext {
         (surface, config)
     }
 
-    pub(super) fn configure_surface(
-        &self,
-        surface: &wgpu::Surface,
-        config: &wgpu::SurfaceConfiguration,
-    ) {
+    pub fn configure_surface(&self, surface: &wgpu::Surface, config: &wgpu::SurfaceConfiguration) {
         surface.configure(&self.device, config)
     }
 
@@ -0,0 +1,86 @@
+use naga::{ResourceBinding, ShaderStage};
+
+use crate::shader_source::{self, ShaderError, STAGE_BINDINGS};
+
+/// Size in bytes of the uniform block custom shaders can declare at
+/// `@group(1) @binding(1)`, filled from the parameters of
+/// [`BackdropFilter::Custom`](crate::BackdropFilter::Custom).
+pub const CUSTOM_UNIFORM_SIZE: u64 = 64;
+
+/// Fragment entry point of custom shaders.
+pub(crate) const FS_CUSTOM: &str = "fs_main";
+
+/// A backdrop shader of an app's own, registered with
+/// [`BlurShaders::register_custom`](crate::BlurShaders::register_custom).
+///
+/// `source` only has to hold the fragment entry point `fs_main`, which runs
+/// over the region like the built-in colour stage does. It's compiled with
+/// the same bindings: the `screen` size in points, the texture `t_diffuse`
+/// with the output of the stage before and its sampler `s_diffuse`, the
+/// `region` uniforms and helpers like `load`, `coverage` or
+/// `gamma_from_linear`. Its parameters are a uniform block of up to
+/// [`CUSTOM_UNIFORM_SIZE`] bytes of its own:
+///
+/// ```wgsl
+/// struct Glass {
+///     strength: f32,
+/// }
+///
+/// @group(1) @binding(1)
+/// var<uniform> glass: Glass;
+///
+/// @fragment
+/// fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+///     let offset = vec2<f32>(sin(in.clip_position.y / 8.0), 0.0) * glass.strength;
+///     let dims = vec2<f32>(textureDimensions(t_diffuse));
+///     let uv = (in.clip_position.xy + offset) / dims;
+///
+///     return vec4<f32>(textureSampleLevel(t_diffuse, s_diffuse, uv, 0.0).rgb, 1.0);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct CustomShaderDescriptor<'a> {
+    pub label: &'a str,
+    pub source: &'a str,
+    /// How far around a pixel the shader reads `t_diffuse`, in points, so
+    /// the stages before it fill that much around the region.
+    pub reach: f32,
+}
+
+/// Handle of a registered custom shader, only run by the
+/// [`WindowTexture`](crate::WindowTexture)s of the
+/// [`BlurShaders`](crate::BlurShaders) it was registered with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CustomShader {
+    /// [`BlurShaders::id`](crate::BlurShaders) of the shaders it's one of
+    pub(crate) shaders: u64,
+    pub(crate) index: usize,
+    pub(crate) reach: f32,
+}
+
+const CUSTOM_UNIFORM_BINDING: (u32, u32) = (1, 1);
+
+/// Checks the complete `source` of a custom shader, see
+/// [`shader_source::parse`], and that it fits the pipeline of the stages.
+pub(crate) fn validate(label: &str, source: &str) -> Result<(), ShaderError> {
+    let module = shader_source::parse(label, source)?;
+    shader_source::require_entry_point(&module, ShaderStage::Fragment, FS_CUSTOM)?;
+    shader_source::check_bindings(&module, &STAGE_BINDINGS)?;
+
+    for (_, global) in module.global_variables.iter() {
+        let Some(ResourceBinding { group, binding }) = global.binding else {
+            continue;
+        };
+        let size = module.types[global.ty].inner.size(&module.constants);
+
+        if (group, binding) == CUSTOM_UNIFORM_BINDING && u64::from(size) > CUSTOM_UNIFORM_SIZE {
+            let name = global.name.as_deref().unwrap_or("_");
+
+            return Err(ShaderError::Binding(format!(
+                "`{name}` takes {size} bytes, custom uniforms are limited to {CUSTOM_UNIFORM_SIZE}"
+            )));
+        }
+    }
+
+    Ok(())
+}
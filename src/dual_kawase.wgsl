@@ -0,0 +1,101 @@
+var<private> v_positions: array<vec2<f32>, 4> = array<vec2<f32>, 4>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(-1.0, 1.0),
+    vec2<f32>(1.0, 1.0),
+);
+
+struct VertexOut {
+    @location(0) tex_coords: vec2<f32>,
+    @builtin(position) clip_position: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> screen: vec2<f32>;
+@group(0) @binding(1)
+var t_diffuse: texture_2d<f32>;
+@group(0) @binding(2)
+var s_diffuse: sampler;
+
+// covers `area` (in points) with the quad, every level of the chain spans
+// the whole screen so the texture coordinates are relative to the screen
+fn quad(area: vec4<f32>, v_idx: u32) -> VertexOut {
+    var out: VertexOut;
+    let vert = v_positions[v_idx];
+
+    let corner = vec2<f32>(
+        (vert.x + 1.0) / 2.0,
+        (-vert.y + 1.0) / 2.0,
+    );
+
+    out.tex_coords = mix(area.xy, area.zw, corner) / screen;
+    out.clip_position = vec4<f32>(
+        out.tex_coords.x * 2.0 - 1.0,
+        1.0 - out.tex_coords.y * 2.0,
+        0.0,
+        1.0
+    );
+
+    return out;
+}
+
+@vertex
+fn vs_chain(
+    @builtin(vertex_index) v_idx: u32
+) -> VertexOut {
+    // the chain is picked to reach at least the radius but may overshoot it,
+    // by less than twice the radius, so the intermediate levels have to be
+    // filled that far around the area
+    let margin = 2.0 * region.radius;
+
+    return quad(region.area + vec4<f32>(-margin, -margin, margin, margin), v_idx);
+}
+
+@vertex
+fn vs_final(
+    @builtin(vertex_index) v_idx: u32
+) -> VertexOut {
+    return quad(region.area, v_idx);
+}
+
+fn sample(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(t_diffuse, s_diffuse, uv, 0.0).rgb;
+}
+
+@fragment
+fn fs_downsample(
+    in: VertexOut
+) -> @location(0) vec4<f32> {
+    let uv = in.tex_coords;
+    let halfpixel = 0.5 / vec2<f32>(textureDimensions(t_diffuse));
+
+    var rgb = sample(uv) * 4.0;
+    rgb += sample(uv - halfpixel);
+    rgb += sample(uv + halfpixel);
+    rgb += sample(uv + vec2<f32>(halfpixel.x, -halfpixel.y));
+    rgb += sample(uv - vec2<f32>(halfpixel.x, -halfpixel.y));
+
+    return vec4<f32>(rgb / 8.0, 1.0);
+}
+
+fn upsample(uv: vec2<f32>) -> vec3<f32> {
+    let halfpixel = 0.5 / vec2<f32>(textureDimensions(t_diffuse));
+
+    var rgb = sample(uv + vec2<f32>(-halfpixel.x * 2.0, 0.0));
+    rgb += sample(uv + vec2<f32>(-halfpixel.x, halfpixel.y)) * 2.0;
+    rgb += sample(uv + vec2<f32>(0.0, halfpixel.y * 2.0));
+    rgb += sample(uv + vec2<f32>(halfpixel.x, halfpixel.y)) * 2.0;
+    rgb += sample(uv + vec2<f32>(halfpixel.x * 2.0, 0.0));
+    rgb += sample(uv + vec2<f32>(halfpixel.x, -halfpixel.y)) * 2.0;
+    rgb += sample(uv + vec2<f32>(0.0, -halfpixel.y * 2.0));
+    rgb += sample(uv + vec2<f32>(-halfpixel.x, -halfpixel.y)) * 2.0;
+
+    return rgb / 12.0;
+}
+
+@fragment
+fn fs_upsample(
+    in: VertexOut
+) -> @location(0) vec4<f32> {
+    return vec4<f32>(upsample(in.tex_coords), 1.0);
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use egui::{epaint::Primitive, ClippedPrimitive, Context, LayerId, PaintCallback, Rect, Shape};
+
+/// Marks where every layer ends in what egui paints this frame, for
+/// [`WindowTexture::prepare`] to tell which layers are below a region of
+/// [`layer_blur_callback`]. Call it once the UI is done, last thing in the
+/// closure passed to [`egui::Context::run`].
+///
+/// The areas windows, popups, menus and tooltips are shown in are marked, as
+/// is the background layer panels paint to. Shapes painted to any other layer
+/// with [`egui::Context::layer_painter`] count as part of the next of those
+/// painted after them.
+///
+/// [`WindowTexture::prepare`]: crate::WindowTexture::prepare
+/// [`layer_blur_callback`]: crate::layer_blur_callback
+pub fn mark_layers(ctx: &Context) {
+    let layers: Vec<LayerId> = ctx.memory(|memory| memory.layer_ids().collect());
+
+    for layer in [LayerId::background()].into_iter().chain(layers) {
+        // without a rect egui-wgpu never runs it, should it be left in
+        ctx.layer_painter(layer).add(Shape::Callback(PaintCallback {
+            rect: Rect::NOTHING,
+            callback: Arc::new(LayerEnd(layer)),
+        }));
+    }
+}
+
+/// Paint callback put last on a layer by [`mark_layers`].
+pub(crate) struct LayerEnd(pub LayerId);
+
+/// The ends of the marked layers in a frame's paint jobs, in the order they
+/// are painted.
+pub(crate) struct LayerEnds(Vec<(usize, LayerId)>);
+
+impl LayerEnds {
+    pub fn find(paint_jobs: &[ClippedPrimitive]) -> Self {
+        let ends = paint_jobs
+            .iter()
+            .enumerate()
+            .filter_map(
+                |(job, ClippedPrimitive { primitive, .. })| match primitive {
+                    Primitive::Callback(callback) => callback
+                        .callback
+                        .downcast_ref::<LayerEnd>()
+                        .map(|LayerEnd(layer)| (job, *layer)),
+                    Primitive::Mesh(_) => None,
+                },
+            )
+            .collect();
+
+        Self(ends)
+    }
+
+    /// Index of the first paint job of `layer`, given the job at `job` is
+    /// painted on it. That's `job` itself if the layers weren't marked or
+    /// the job is on another layer.
+    pub fn start(&self, job: usize, layer: LayerId) -> usize {
+        let next = self.0.partition_point(|&(end, _)| end < job);
+
+        match self.0.get(next) {
+            Some(&(_, end_layer)) if end_layer == layer => next
+                .checked_sub(1)
+                .map_or(0, |previous| self.0[previous].0 + 1),
+            _ => job,
+        }
+    }
+}
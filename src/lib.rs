@@ -0,0 +1,84 @@
+//! Blurred backdrops for egui windows rendered with egui-wgpu.
+//!
+//! egui is rendered into a [`WindowTexture`] instead of the surface, which
+//! stops at every callback of [`blur_callback`] to blur what has been painted
+//! below it, cut to a rounded rect or any shape with [`shape_blur_callback`],
+//! or run any other chain of [`BackdropFilter`]s over it, including
+//! shaders of the app's own registered with [`BlurShaders::register_custom`].
+//! [`BlurWindow`], [`BlurArea`] and [`BlurPanel`] paint egui's containers
+//! over such callbacks. The regions of windows, areas and popups are filtered
+//! from exactly the layers below their own, given the app calls
+//! [`mark_layers`] at the end of its UI. For the frames egui paints around
+//! windows, popups, menus and tooltips of its own, a [`BlurStyle`] set with
+//! [`set_blur_style`] has [`blur_frames`] do the same, which [`blur_frame`]
+//! overrides for single layers, as [`BlurPopup`] does for the popups it
+//! shows. An app owning its wgpu device integrates it like this:
+//!
+//! ```ignore
+//! // once per device
+//! let shaders = BlurShaders::new(&device);
+//!
+//! // once the surface is created
+//! let target = RenderTarget { format, size, pixels_per_point };
+//! let mut window_texture = WindowTexture::new(&device, &shaders, &target);
+//!
+//! // whenever it's resized, which keeps the pipelines
+//! window_texture.resize(&device, &queue, &target);
+//!
+//! // every frame, in place of rendering egui to the surface, dropping the
+//! // cached backdrops if egui's textures changed
+//! if !textures_delta.set.is_empty() {
+//!     window_texture.invalidate_cache();
+//! }
+//! window_texture.prepare(&device, &queue, &mut encoder, &mut paint_jobs);
+//! egui_renderer.update_buffers(&device, &queue, &mut encoder, &paint_jobs, &screen);
+//! window_texture.render(&mut encoder, &egui_renderer, &mut paint_jobs, &screen);
+//! // then draw it to the surface with `window_texture.copy_to(&mut surface_pass)`
+//! ```
+//!
+//! [`RenderContext`] sets up an instance, device and the shaders for apps
+//! which don't have one yet, and [`OffscreenTarget`] stands in for a surface
+//! when rendering without a window. While working on the shaders,
+//! [`RenderContext::watch_shaders`] reloads them from the source directory
+//! as they are saved. See `examples/demo` for a complete program.
+
+mod backdrop_filter;
+mod blur_area;
+mod blur_cache;
+mod blur_callback;
+mod blur_panel;
+mod blur_popup;
+mod blur_style;
+mod blur_window;
+mod color_matrix;
+mod context;
+mod custom_shader;
+mod layers;
+mod pipeline;
+pub mod reference;
+mod render_target;
+mod screenshot;
+mod shader_source;
+mod window_texture;
+
+pub use backdrop_filter::BackdropFilter;
+pub use blur_area::BlurArea;
+pub use blur_callback::{
+    blur_callback, layer_blur_callback, layer_shape_blur_callback, shape_blur_callback,
+};
+pub use blur_panel::{BlurPanel, Panel};
+pub use blur_popup::BlurPopup;
+pub use blur_style::{blur_frame, blur_frames, blur_style, set_blur_style, BlurStyle};
+pub use blur_window::BlurWindow;
+pub use color_matrix::ColorMatrix;
+pub use context::{BlurShaders, RenderContext};
+pub use custom_shader::{CustomShader, CustomShaderDescriptor, CUSTOM_UNIFORM_SIZE};
+pub use layers::mark_layers;
+pub use pipeline::{
+    kawase_iterations, BlurMode, BlurParams, PipelineRegistry, Region, RegionUniforms,
+    WindowPipelineRegistry, WindowResources, MAX_KAWASE_LEVELS,
+};
+pub use render_target::{OffscreenTarget, RenderTarget};
+pub use screenshot::read_texture;
+pub use shader_source::{ShaderError, ShaderSources, SHADER_SOURCE_DIR};
+pub use window_texture::WindowTexture;
@@ -6,31 +6,676 @@ use wgpu::{
 };
 use winit::dpi::LogicalSize;
 
-use crate::{context::RenderContext, surface::SurfaceRenderer};
+use crate::{
+    blur_callback::PreparedMask,
+    color_matrix::ColorMatrix,
+    context::BlurShaders,
+    custom_shader::{CustomShader, CUSTOM_UNIFORM_SIZE, FS_CUSTOM},
+    render_target::RenderTarget,
+};
+
+pub(crate) const VS_MAIN: &str = "vs_main";
+pub(crate) const FS_MAIN: &str = "fs_main";
+
+pub(crate) const VS_BLUR_HORIZONTAL: &str = "vs_blur_horizontal";
+pub(crate) const FS_BLUR_HORIZONTAL: &str = "fs_blur_horizontal";
+pub(crate) const VS_BLUR_VERTICAL: &str = "vs_blur_vertical";
+pub(crate) const FS_BLUR_VERTICAL: &str = "fs_blur_vertical";
 
-const VS_MAIN: &str = "vs_main";
-const FS_MAIN: &str = "fs_main";
+pub(crate) const VS_KAWASE_CHAIN: &str = "vs_chain";
+pub(crate) const VS_KAWASE_FINAL: &str = "vs_final";
+pub(crate) const FS_KAWASE_DOWNSAMPLE: &str = "fs_downsample";
+pub(crate) const FS_KAWASE_UPSAMPLE: &str = "fs_upsample";
+
+pub(crate) const VS_AREA: &str = "vs_area";
+pub(crate) const VS_RECT: &str = "vs_rect";
+pub(crate) const FS_COLOR: &str = "fs_color";
+pub(crate) const FS_COMPOSITE: &str = "fs_composite";
+pub(crate) const VS_MASK: &str = "vs_mask";
+pub(crate) const FS_MASK: &str = "fs_mask";
+pub(crate) const FS_COMPOSITE_MASK: &str = "fs_composite_mask";
+
+/// Number of downsampled levels (half, quarter, eighth, ... resolution)
+/// available to the dual kawase blur.
+pub const MAX_KAWASE_LEVELS: usize = 6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BlurMode {
+    /// Separable gaussian at full resolution, cost grows linearly with the
+    /// radius.
+    #[default]
+    Gaussian,
+    /// Dual kawase over a chain of downsampled levels, cost barely grows with
+    /// the radius, which makes it the choice for large radii.
+    DualKawase,
+}
+
+/// Look of a blurred region, the filters most regions need in one place.
+/// It turns into the list of [`BackdropFilter`](crate::BackdropFilter)s
+/// `[blur, color_matrix, tint, opacity]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlurParams {
+    pub mode: BlurMode,
+    /// How far colours spread, in points. The gaussian reaches this far at
+    /// three standard deviations.
+    pub radius: f32,
+    /// Colour mixed into the blurred backdrop, its alpha is ignored.
+    pub tint: egui::Color32,
+    /// How much of `tint` is mixed in, from `0.0` to `1.0`.
+    pub tint_strength: f32,
+    /// Opacity of the blurred backdrop over the unblurred one.
+    pub opacity: f32,
+    /// Colour adjustments of the blurred backdrop, applied before the tint.
+    pub color_matrix: ColorMatrix,
+}
+
+impl Default for BlurParams {
+    fn default() -> Self {
+        Self {
+            mode: BlurMode::Gaussian,
+            radius: 27.0,
+            tint: egui::Color32::WHITE,
+            tint_strength: 0.0,
+            opacity: 1.0,
+            color_matrix: ColorMatrix::IDENTITY,
+        }
+    }
+}
+
+/// How far `n` iterations of the dual kawase chain reach, in physical pixels,
+/// divided by `2^n`. Every level halves the resolution, which doubles how far
+/// the same taps spread colours: measured on a line, `n` iterations spread it
+/// with a standard deviation of about `0.78 * 2^n` pixels, which reaches
+/// three standard deviations, as far as the gaussian's radius, at
+/// `2.3 * 2^n`.
+const KAWASE_REACH_PER_LEVEL: f32 = 2.3;
+
+/// Number of downsample/upsample iterations for the dual kawase chain to
+/// reach `radius` physical pixels, at least one and at most
+/// [`MAX_KAWASE_LEVELS`]. The chain reaches as far as the gaussian of the
+/// same radius, up to twice that where the radius falls between iterations.
+pub fn kawase_iterations(radius: f32) -> usize {
+    let iterations = (radius / KAWASE_REACH_PER_LEVEL).log2().ceil().max(1.0) as usize;
+
+    iterations.min(MAX_KAWASE_LEVELS)
+}
 
 pub struct WindowPipelineRegistry {
     pub copy_pipeline: wgpu::RenderPipeline,
     pub copy_bind_group: wgpu::BindGroup,
 
-    pub copy_back_pipeline: wgpu::RenderPipeline,
-    pub copy_back_bind_group: wgpu::BindGroup,
+    pub blur_horizontal_pipeline: wgpu::RenderPipeline,
+    pub blur_vertical_pipeline: wgpu::RenderPipeline,
+
+    pub kawase_downsample_pipeline: wgpu::RenderPipeline,
+    pub kawase_upsample_pipeline: wgpu::RenderPipeline,
+    pub kawase_upsample_final_pipeline: wgpu::RenderPipeline,
+
+    pub color_pipeline: wgpu::RenderPipeline,
+    /// `[i]` runs the custom shader `i` of [`BlurShaders::custom`].
+    pub custom_pipelines: Vec<wgpu::RenderPipeline>,
+    /// which [`BlurShaders`] the custom pipelines were created from
+    shaders: u64,
+    /// Blends the last stage of a region onto the window texture.
+    pub composite_pipeline: wgpu::RenderPipeline,
+    /// Draws the last stage of a region into the other ping-pong texture,
+    /// with its tessellated mask's coverage as the alpha.
+    pub mask_pipeline: wgpu::RenderPipeline,
+    /// Same as `composite_pipeline`, cut to the coverage `mask_pipeline`
+    /// drew rather than the rounded rect.
+    pub composite_mask_pipeline: wgpu::RenderPipeline,
 
-    pub blur_rect_pipeline: wgpu::RenderPipeline,
-    pub blur_rect_bind_group: wgpu::BindGroup,
+    /// Every stage's pass reads its input through one of these bind groups,
+    /// they all share a layout.
+    pub window_bind_group: wgpu::BindGroup,
+    pub ping_pong_bind_groups: [wgpu::BindGroup; 2],
+    /// `[i]` reads the dual kawase level `i`
+    pub kawase_bind_groups: Vec<wgpu::BindGroup>,
 
-    rect_uniform: wgpu::Buffer,
+    pub regions: RegionUniforms,
+    pub masks: MaskBuffers,
+
+    /// what the pipelines are created with again on
+    /// [`Self::reload_pipelines`], and the bind groups on [`Self::rebind`]
+    format: wgpu::TextureFormat,
+    copy_bind_group_layout: wgpu::BindGroupLayout,
+    copy_pipeline_layout: wgpu::PipelineLayout,
+    stage_bind_group_layout: wgpu::BindGroupLayout,
+    stage_pipeline_layout: wgpu::PipelineLayout,
 }
 
 impl WindowPipelineRegistry {
-    pub fn set_rect(&self, rect: egui::epaint::Rect, queue: &wgpu::Queue) {
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    /// Creates the bind groups again for `resources`, e.g. after the window
+    /// texture was resized. The pipelines stay as they are.
+    pub fn rebind(&mut self, device: &wgpu::Device, resources: &WindowResources) {
+        WindowBindGroups {
+            copy_bind_group: self.copy_bind_group,
+            window_bind_group: self.window_bind_group,
+            ping_pong_bind_groups: self.ping_pong_bind_groups,
+            kawase_bind_groups: self.kawase_bind_groups,
+        } = WindowBindGroups::new(
+            device,
+            &self.copy_bind_group_layout,
+            &self.stage_bind_group_layout,
+            resources,
+        );
+    }
+
+    /// Creates the pipelines again from `shaders`, e.g. after
+    /// [`BlurShaders::reload`]. Should wgpu reject any of them, the
+    /// pipelines are left as they were.
+    pub fn reload_pipelines(
+        &mut self,
+        device: &wgpu::Device,
+        shaders: &BlurShaders,
+    ) -> Result<(), wgpu::Error> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let copy_pipeline = PipelineRegistry::create_copy_pipeline(
+            device,
+            shaders,
+            self.format,
+            &self.copy_pipeline_layout,
+        );
+        let stage_pipelines = PipelineRegistry::create_stage_pipelines(
+            device,
+            shaders,
+            self.format,
+            &self.stage_pipeline_layout,
+        );
+
+        if let Some(err) = pollster::block_on(device.pop_error_scope()) {
+            return Err(err);
+        }
+
+        StagePipelines {
+            blur_horizontal_pipeline: self.blur_horizontal_pipeline,
+            blur_vertical_pipeline: self.blur_vertical_pipeline,
+            kawase_downsample_pipeline: self.kawase_downsample_pipeline,
+            kawase_upsample_pipeline: self.kawase_upsample_pipeline,
+            kawase_upsample_final_pipeline: self.kawase_upsample_final_pipeline,
+            color_pipeline: self.color_pipeline,
+            custom_pipelines: self.custom_pipelines,
+            composite_pipeline: self.composite_pipeline,
+            mask_pipeline: self.mask_pipeline,
+            composite_mask_pipeline: self.composite_mask_pipeline,
+        } = stage_pipelines;
+        self.copy_pipeline = copy_pipeline;
+        self.shaders = shaders.id();
+
+        Ok(())
+    }
+
+    /// Whether there's a pipeline for `shader`, which isn't the case if it
+    /// was registered after they were created or with other shaders.
+    pub(crate) fn runs(&self, shader: &CustomShader) -> bool {
+        shader.shaders == self.shaders && shader.index < self.custom_pipelines.len()
+    }
+}
+
+/// What the bind groups of a window texture read, which changes whenever it's
+/// resized.
+pub struct WindowResources<'a> {
+    pub view: &'a wgpu::TextureView,
+    pub ping_pong_views: &'a [wgpu::TextureView; 2],
+    pub kawase_views: &'a [wgpu::TextureView],
+    pub sampler: &'a wgpu::Sampler,
+    pub window_size_uniform: &'a wgpu::Buffer,
+}
+
+/// The bind groups of [`WindowPipelineRegistry`], over the
+/// [`WindowResources`] of its window texture.
+struct WindowBindGroups {
+    copy_bind_group: wgpu::BindGroup,
+    window_bind_group: wgpu::BindGroup,
+    ping_pong_bind_groups: [wgpu::BindGroup; 2],
+    kawase_bind_groups: Vec<wgpu::BindGroup>,
+}
+
+impl WindowBindGroups {
+    fn new(
+        device: &wgpu::Device,
+        copy_layout: &wgpu::BindGroupLayout,
+        stage_layout: &wgpu::BindGroupLayout,
+        resources: &WindowResources,
+    ) -> Self {
+        let WindowResources {
+            view,
+            ping_pong_views,
+            kawase_views,
+            sampler,
+            window_size_uniform,
+        } = *resources;
+
+        let copy_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: copy_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: None,
+        });
+
+        let create_blur_bind_group = |source: &wgpu::TextureView, label| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: stage_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: window_size_uniform.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(source),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+                label: Some(label),
+            })
+        };
+
+        Self {
+            copy_bind_group,
+            window_bind_group: create_blur_bind_group(view, "window"),
+            ping_pong_bind_groups: ping_pong_views
+                .each_ref()
+                .map(|view| create_blur_bind_group(view, "ping_pong")),
+            kawase_bind_groups: kawase_views
+                .iter()
+                .map(|view| create_blur_bind_group(view, "kawase"))
+                .collect(),
+        }
+    }
+}
+
+/// The pipelines of the stages of a region's filter chain and its
+/// composite, which share a layout.
+struct StagePipelines {
+    blur_horizontal_pipeline: wgpu::RenderPipeline,
+    blur_vertical_pipeline: wgpu::RenderPipeline,
+    kawase_downsample_pipeline: wgpu::RenderPipeline,
+    kawase_upsample_pipeline: wgpu::RenderPipeline,
+    kawase_upsample_final_pipeline: wgpu::RenderPipeline,
+    color_pipeline: wgpu::RenderPipeline,
+    custom_pipelines: Vec<wgpu::RenderPipeline>,
+    composite_pipeline: wgpu::RenderPipeline,
+    mask_pipeline: wgpu::RenderPipeline,
+    composite_mask_pipeline: wgpu::RenderPipeline,
+}
+
+/// Size of [`Region`] as laid out in the shaders' `Region` struct.
+const REGION_SIZE: u64 = 128;
+
+/// Uniforms of one pass of a region's filter chain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    /// Part of the target the pass covers, in points.
+    pub area: egui::Rect,
+    /// Rect of the region, which its composite is cut to with `rounding`.
+    pub rect: egui::Rect,
+    pub rounding: egui::Rounding,
+    pub tint: egui::Color32,
+    pub tint_strength: f32,
+    pub color_matrix: ColorMatrix,
+    /// Reach of a blur, in points.
+    pub radius: f32,
+    pub noise: f32,
+    pub opacity: f32,
+    pub pixels_per_point: f32,
+    /// Uniform block of a custom shader, which follows the region's in its
+    /// slot.
+    pub custom: [f32; 16],
+}
+
+impl Region {
+    /// A pass over `rect` which leaves the colours as they are.
+    pub fn new(rect: egui::Rect, rounding: egui::Rounding, pixels_per_point: f32) -> Self {
+        Self {
+            area: rect,
+            rect,
+            rounding,
+            tint: egui::Color32::WHITE,
+            tint_strength: 0.0,
+            color_matrix: ColorMatrix::IDENTITY,
+            radius: 0.0,
+            noise: 0.0,
+            opacity: 1.0,
+            pixels_per_point,
+            custom: [0.0; 16],
+        }
+    }
+}
+
+/// Uniforms of every pass drawn for the regions of this frame, each in its
+/// own slot of a uniform buffer which is bound with a dynamic offset, so any
+/// number of regions can be drawn with their own look.
+pub struct RegionUniforms {
+    buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+
+    stride: u64,
+    /// Where the custom uniforms start in a slot, bindings have to be
+    /// aligned like dynamic offsets.
+    custom_offset: u64,
+    capacity: usize,
+    len: usize,
+}
+
+impl RegionUniforms {
+    const INITIAL_CAPACITY: usize = 4;
+
+    fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("blur_region"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: NonZeroU64::new(REGION_SIZE),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: NonZeroU64::new(CUSTOM_UNIFORM_SIZE),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let custom_offset = REGION_SIZE.div_ceil(alignment) * alignment;
+        let stride = custom_offset + CUSTOM_UNIFORM_SIZE.div_ceil(alignment) * alignment;
+
+        let buffer = Self::create_buffer(device, stride, Self::INITIAL_CAPACITY);
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &buffer, custom_offset);
+
+        Self {
+            buffer,
+            bind_group_layout,
+            bind_group,
+            stride,
+            custom_offset,
+            capacity: Self::INITIAL_CAPACITY,
+            len: 0,
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, stride: u64, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("blur_region"),
+            size: stride * capacity as u64,
+            usage: wgpu::BufferUsages::UNIFORM
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        buffer: &wgpu::Buffer,
+        custom_offset: u64,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer,
+                        offset: 0,
+                        size: NonZeroU64::new(REGION_SIZE),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer,
+                        offset: custom_offset,
+                        size: NonZeroU64::new(CUSTOM_UNIFORM_SIZE),
+                    }),
+                },
+            ],
+            label: Some("blur_region"),
+        })
+    }
+
+    /// Frees all slots, to be called once per frame before the paint
+    /// callbacks are prepared.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Writes `region` to the next free slot and returns the dynamic offset
+    /// to draw it with, of both its bindings.
+    pub fn push(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        region: &Region,
+    ) -> u32 {
+        if self.len == self.capacity {
+            // the slots written so far are only staged on the queue, they
+            // land in the old buffer before the encoder copies them over
+            let capacity = self.capacity * 2;
+            let buffer = Self::create_buffer(device, self.stride, capacity);
+
+            encoder.copy_buffer_to_buffer(
+                &self.buffer,
+                0,
+                &buffer,
+                0,
+                self.stride * self.capacity as u64,
+            );
+
+            self.bind_group = Self::create_bind_group(
+                device,
+                &self.bind_group_layout,
+                &buffer,
+                self.custom_offset,
+            );
+            self.buffer = buffer;
+            self.capacity = capacity;
+        }
+
+        let Region {
+            area,
+            rect,
+            rounding,
+            ..
+        } = *region;
+
+        // the shaders work in linear space
+        let tint = egui::Rgba::from(region.tint);
+        let offset = self.stride * self.len as u64;
+
+        let [red, green, blue] = region.color_matrix.rows;
+
         queue.write_buffer(
-            &self.rect_uniform,
-            0,
-            bytemuck::cast_slice(&[rect.left(), rect.top(), rect.right(), rect.bottom()]),
+            &self.buffer,
+            offset,
+            bytemuck::cast_slice(&[
+                [area.left(), area.top(), area.right(), area.bottom()],
+                [rect.left(), rect.top(), rect.right(), rect.bottom()],
+                [rounding.nw, rounding.ne, rounding.sw, rounding.se],
+                [tint.r(), tint.g(), tint.b(), region.tint_strength],
+                red,
+                green,
+                blue,
+                [
+                    region.radius,
+                    region.noise,
+                    region.opacity,
+                    region.pixels_per_point,
+                ],
+            ]),
+        );
+        queue.write_buffer(
+            &self.buffer,
+            offset + self.custom_offset,
+            bytemuck::cast_slice(&region.custom),
         );
+
+        self.len += 1;
+
+        offset as u32
+    }
+}
+
+/// Vertices and indices of the tessellated masks of this frame's regions,
+/// in a vertex and an index buffer which grow as [`RegionUniforms`] does.
+pub struct MaskBuffers {
+    pub vertices: wgpu::Buffer,
+    pub indices: wgpu::Buffer,
+    /// in vertices and indices
+    vertex_capacity: usize,
+    index_capacity: usize,
+    vertex_len: usize,
+    index_len: usize,
+}
+
+impl MaskBuffers {
+    const INITIAL_CAPACITY: usize = 256;
+
+    const VERTEX_SIZE: usize = std::mem::size_of::<egui::epaint::Vertex>();
+    const INDEX_SIZE: usize = std::mem::size_of::<u32>();
+
+    fn new(device: &wgpu::Device) -> Self {
+        Self {
+            vertices: Self::create_buffer(
+                device,
+                wgpu::BufferUsages::VERTEX,
+                Self::VERTEX_SIZE * Self::INITIAL_CAPACITY,
+            ),
+            indices: Self::create_buffer(
+                device,
+                wgpu::BufferUsages::INDEX,
+                Self::INDEX_SIZE * Self::INITIAL_CAPACITY,
+            ),
+            vertex_capacity: Self::INITIAL_CAPACITY,
+            index_capacity: Self::INITIAL_CAPACITY,
+            vertex_len: 0,
+            index_len: 0,
+        }
+    }
+
+    fn create_buffer(
+        device: &wgpu::Device,
+        usage: wgpu::BufferUsages,
+        size: usize,
+    ) -> wgpu::Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("blur_mask"),
+            size: size as u64,
+            usage: usage | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Replaces `buffer`, holding `len` elements of `size` bytes, with one
+    /// of at least `needed` elements, doubling `capacity` as often as that
+    /// takes.
+    fn grow(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        buffer: &mut wgpu::Buffer,
+        capacity: &mut usize,
+        (len, needed, size): (usize, usize, usize),
+    ) {
+        if needed <= *capacity {
+            return;
+        }
+
+        while *capacity < needed {
+            *capacity *= 2;
+        }
+
+        let grown = Self::create_buffer(device, buffer.usage(), size * *capacity);
+        // as with the region uniforms, what was written so far lands in the
+        // old buffer before the encoder copies it over
+        encoder.copy_buffer_to_buffer(buffer, 0, &grown, 0, (size * len) as u64);
+        *buffer = grown;
+    }
+
+    /// Frees the buffers' contents, to be called once per frame before the
+    /// paint callbacks are prepared.
+    pub fn clear(&mut self) {
+        self.vertex_len = 0;
+        self.index_len = 0;
+    }
+
+    /// Writes `mesh` after the masks pushed before, returning where it is.
+    pub(crate) fn push(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        mesh: &egui::Mesh,
+    ) -> PreparedMask {
+        let vertex_end = self.vertex_len + mesh.vertices.len();
+        let index_end = self.index_len + mesh.indices.len();
+
+        Self::grow(
+            device,
+            encoder,
+            &mut self.vertices,
+            &mut self.vertex_capacity,
+            (self.vertex_len, vertex_end, Self::VERTEX_SIZE),
+        );
+        Self::grow(
+            device,
+            encoder,
+            &mut self.indices,
+            &mut self.index_capacity,
+            (self.index_len, index_end, Self::INDEX_SIZE),
+        );
+
+        queue.write_buffer(
+            &self.vertices,
+            (Self::VERTEX_SIZE * self.vertex_len) as u64,
+            bytemuck::cast_slice(&mesh.vertices),
+        );
+        queue.write_buffer(
+            &self.indices,
+            (Self::INDEX_SIZE * self.index_len) as u64,
+            bytemuck::cast_slice(&mesh.indices),
+        );
+
+        let mask = PreparedMask {
+            base_vertex: self.vertex_len as i32,
+            indices: self.index_len as u32..index_end as u32,
+        };
+
+        self.vertex_len = vertex_end;
+        self.index_len = index_end;
+
+        mask
     }
 }
 
@@ -38,11 +683,9 @@ pub struct PipelineRegistry {}
 
 impl PipelineRegistry {
     pub fn create_window_size_buffer(
-        context: &RenderContext,
+        device: &wgpu::Device,
         size: LogicalSize<f32>,
     ) -> wgpu::Buffer {
-        let (device, ..) = context.get_device();
-
         device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Window Size Buffer"),
             contents: bytemuck::cast_slice(&[size.width, size.height]),
@@ -50,15 +693,9 @@ impl PipelineRegistry {
         })
     }
 
-    fn create_copy_pipeline(
-        context: &RenderContext,
-        surface: &SurfaceRenderer,
-        view: &wgpu::TextureView,
-        sampler: &wgpu::Sampler,
-    ) -> (wgpu::RenderPipeline, wgpu::BindGroup) {
-        let (device, _queue) = context.get_device();
-        let shader = &context.shader_copy_texture;
-
+    /// The bind group layout of the pipeline copying the window texture to
+    /// the target, and the pipeline layout.
+    fn create_copy_layouts(device: &wgpu::Device) -> (wgpu::BindGroupLayout, wgpu::PipelineLayout) {
         let copy_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: None,
@@ -84,31 +721,63 @@ impl PipelineRegistry {
                 ],
             });
 
-        let copy_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &copy_bind_group_layout,
+        let copy_render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&copy_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        (copy_bind_group_layout, copy_render_pipeline_layout)
+    }
+
+    fn create_stage_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("blur_rect"),
             entries: &[
-                wgpu::BindGroupEntry {
+                wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&view),
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: NonZeroU64::new(8),
+                    },
+                    count: None,
                 },
-                wgpu::BindGroupEntry {
+                wgpu::BindGroupLayoutEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    // This should match the filterable field of the
+                    // corresponding Texture entry above.
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
                 },
             ],
-            label: None,
-        });
+        })
+    }
 
-        let copy_render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: None,
-                bind_group_layouts: &[&copy_bind_group_layout], // NEW!
-                push_constant_ranges: &[],
-            });
+    fn create_copy_pipeline(
+        device: &wgpu::Device,
+        shaders: &BlurShaders,
+        format: wgpu::TextureFormat,
+        layout: &wgpu::PipelineLayout,
+    ) -> wgpu::RenderPipeline {
+        let shader = &shaders.copy_texture;
 
-        let copy_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: None,
-            layout: Some(&copy_render_pipeline_layout),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
                 module: shader,
                 entry_point: VS_MAIN,
@@ -118,7 +787,7 @@ impl PipelineRegistry {
                 module: shader,
                 entry_point: FS_MAIN,
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: surface.format(),
+                    format,
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -139,129 +808,32 @@ impl PipelineRegistry {
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-        });
-
-        return (copy_pipeline, copy_bind_group);
+        })
     }
 
-    pub fn generate_for_window(
-        context: &RenderContext,
-        surface: &SurfaceRenderer,
-        view: &wgpu::TextureView,
-        back_view: &wgpu::TextureView,
-        sampler: &wgpu::Sampler,
-        back_sampler: &wgpu::Sampler,
-        window_size_uniform: &wgpu::Buffer,
-    ) -> WindowPipelineRegistry {
-        let (device, queue) = context.get_device();
-
-        let (copy_pipeline, copy_bind_group) =
-            Self::create_copy_pipeline(context, surface, view, sampler);
-
-        let (copy_back_pipeline, copy_back_bind_group) =
-            Self::create_copy_pipeline(context, surface, back_view, back_sampler);
-
-        let size: [f32; 2] = surface.logical_size().cast::<f32>().into();
-        queue.write_buffer(window_size_uniform, 0, bytemuck::cast_slice(&size));
-
-        let rect_uniform = device.create_buffer(&BufferDescriptor {
-            label: None,
-            size: 16,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        let blur_rect_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("blur_rect"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: NonZeroU64::new(8),
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: NonZeroU64::new(16),
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 2,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 3,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        // This should match the filterable field of the
-                        // corresponding Texture entry above.
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None,
-                    },
-                ],
-            });
-
-        let blur_rect_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &blur_rect_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: window_size_uniform.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: rect_uniform.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-            label: Some("diffuse_bind_group"),
-        });
-
-        let blur_rect_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("blur_rect"),
-                bind_group_layouts: &[&blur_rect_bind_group_layout],
-                push_constant_ranges: &[],
-            });
-
-        let blur_rect_shader = &context.shader_blur_rect;
-
-        let blur_rect_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("blur_rect"),
-            layout: Some(&blur_rect_pipeline_layout),
+    fn create_blur_pipeline(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        vs_entry_point: &str,
+        fs_entry_point: &str,
+        blend: wgpu::BlendState,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(fs_entry_point),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
-                module: blur_rect_shader,
-                entry_point: VS_MAIN,
+                module: shader,
+                entry_point: vs_entry_point,
                 buffers: &[],
             },
             fragment: Some(wgpu::FragmentState {
-                module: blur_rect_shader,
-                entry_point: FS_MAIN,
+                module: shader,
+                entry_point: fs_entry_point,
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: surface.format(),
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    format,
+                    blend: Some(blend),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
@@ -281,16 +853,272 @@ impl PipelineRegistry {
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-        });
+        })
+    }
+
+    /// The pipeline drawing the meshes of egui's tessellator, which regions
+    /// are masked with, rather than quads. Where triangles overlap, e.g. on
+    /// the feathered edges, the larger coverage is kept rather than both
+    /// blended.
+    fn create_mask_pipeline(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+    ) -> wgpu::RenderPipeline {
+        // the uv of egui's vertices is left out
+        let attributes = [
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x2,
+                offset: 0,
+                shader_location: 0,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Unorm8x4,
+                offset: 16,
+                shader_location: 1,
+            },
+        ];
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(FS_MASK),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: VS_MASK,
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<egui::epaint::Vertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &attributes,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: FS_MASK,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent::REPLACE,
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Max,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        })
+    }
+
+    fn create_stage_pipeline_layout(
+        device: &wgpu::Device,
+        stage_bind_group_layout: &wgpu::BindGroupLayout,
+        regions: &RegionUniforms,
+    ) -> wgpu::PipelineLayout {
+        device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("blur_rect"),
+            bind_group_layouts: &[stage_bind_group_layout, &regions.bind_group_layout],
+            push_constant_ranges: &[],
+        })
+    }
+
+    /// Creates the pipelines of `shaders` on layouts of their own, returning
+    /// wgpu's error if it rejects them. Checked for a common format, as the
+    /// shaders are shared by windows of any.
+    pub(crate) fn check_pipelines(
+        device: &wgpu::Device,
+        shaders: &BlurShaders,
+    ) -> Result<(), wgpu::Error> {
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let (_, copy_pipeline_layout) = Self::create_copy_layouts(device);
+        let stage_bind_group_layout = Self::create_stage_bind_group_layout(device);
+        let regions = RegionUniforms::new(device);
+        let stage_pipeline_layout =
+            Self::create_stage_pipeline_layout(device, &stage_bind_group_layout, &regions);
+
+        Self::create_copy_pipeline(device, shaders, format, &copy_pipeline_layout);
+        Self::create_stage_pipelines(device, shaders, format, &stage_pipeline_layout);
+
+        match pollster::block_on(device.pop_error_scope()) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn create_stage_pipelines(
+        device: &wgpu::Device,
+        shaders: &BlurShaders,
+        format: wgpu::TextureFormat,
+        layout: &wgpu::PipelineLayout,
+    ) -> StagePipelines {
+        let create_pipeline = |shader, vs_entry_point, fs_entry_point, blend| {
+            Self::create_blur_pipeline(
+                device,
+                format,
+                layout,
+                shader,
+                vs_entry_point,
+                fs_entry_point,
+                blend,
+            )
+        };
+
+        let replace = wgpu::BlendState::REPLACE;
+
+        let blur_horizontal_pipeline = create_pipeline(
+            &shaders.blur_rect,
+            VS_BLUR_HORIZONTAL,
+            FS_BLUR_HORIZONTAL,
+            replace,
+        );
+        let blur_vertical_pipeline = create_pipeline(
+            &shaders.blur_rect,
+            VS_BLUR_VERTICAL,
+            FS_BLUR_VERTICAL,
+            replace,
+        );
+
+        let kawase_downsample_pipeline = create_pipeline(
+            &shaders.dual_kawase,
+            VS_KAWASE_CHAIN,
+            FS_KAWASE_DOWNSAMPLE,
+            replace,
+        );
+        let kawase_upsample_pipeline = create_pipeline(
+            &shaders.dual_kawase,
+            VS_KAWASE_CHAIN,
+            FS_KAWASE_UPSAMPLE,
+            replace,
+        );
+        // the last upsample only has to cover the stage's area
+        let kawase_upsample_final_pipeline = create_pipeline(
+            &shaders.dual_kawase,
+            VS_KAWASE_FINAL,
+            FS_KAWASE_UPSAMPLE,
+            replace,
+        );
+
+        let color_pipeline = create_pipeline(&shaders.backdrop, VS_AREA, FS_COLOR, replace);
+        let custom_pipelines = shaders
+            .custom
+            .iter()
+            .map(|shader| create_pipeline(shader, VS_AREA, FS_CUSTOM, replace))
+            .collect();
+
+        let composite_pipeline = create_pipeline(
+            &shaders.backdrop,
+            VS_RECT,
+            FS_COMPOSITE,
+            wgpu::BlendState::ALPHA_BLENDING,
+        );
+        let mask_pipeline = Self::create_mask_pipeline(device, format, layout, &shaders.backdrop);
+        let composite_mask_pipeline = create_pipeline(
+            &shaders.backdrop,
+            VS_RECT,
+            FS_COMPOSITE_MASK,
+            wgpu::BlendState::ALPHA_BLENDING,
+        );
+
+        StagePipelines {
+            blur_horizontal_pipeline,
+            blur_vertical_pipeline,
+            kawase_downsample_pipeline,
+            kawase_upsample_pipeline,
+            kawase_upsample_final_pipeline,
+            color_pipeline,
+            custom_pipelines,
+            composite_pipeline,
+            mask_pipeline,
+            composite_mask_pipeline,
+        }
+    }
+
+    pub fn generate_for_window(
+        device: &wgpu::Device,
+        shaders: &BlurShaders,
+        target: &RenderTarget,
+        resources: &WindowResources,
+    ) -> WindowPipelineRegistry {
+        let format = target.format;
+
+        let regions = RegionUniforms::new(device);
+
+        let (copy_bind_group_layout, copy_pipeline_layout) = Self::create_copy_layouts(device);
+        let stage_bind_group_layout = Self::create_stage_bind_group_layout(device);
+        let stage_pipeline_layout =
+            Self::create_stage_pipeline_layout(device, &stage_bind_group_layout, &regions);
+
+        let copy_pipeline =
+            Self::create_copy_pipeline(device, shaders, format, &copy_pipeline_layout);
+        let StagePipelines {
+            blur_horizontal_pipeline,
+            blur_vertical_pipeline,
+            kawase_downsample_pipeline,
+            kawase_upsample_pipeline,
+            kawase_upsample_final_pipeline,
+            color_pipeline,
+            custom_pipelines,
+            composite_pipeline,
+            mask_pipeline,
+            composite_mask_pipeline,
+        } = Self::create_stage_pipelines(device, shaders, format, &stage_pipeline_layout);
+
+        let WindowBindGroups {
+            copy_bind_group,
+            window_bind_group,
+            ping_pong_bind_groups,
+            kawase_bind_groups,
+        } = WindowBindGroups::new(
+            device,
+            &copy_bind_group_layout,
+            &stage_bind_group_layout,
+            resources,
+        );
 
         WindowPipelineRegistry {
             copy_pipeline,
             copy_bind_group,
-            copy_back_pipeline,
-            copy_back_bind_group,
-            rect_uniform,
-            blur_rect_bind_group,
-            blur_rect_pipeline,
+            regions,
+            masks: MaskBuffers::new(device),
+            blur_horizontal_pipeline,
+            blur_vertical_pipeline,
+            kawase_downsample_pipeline,
+            kawase_upsample_pipeline,
+            kawase_upsample_final_pipeline,
+            color_pipeline,
+            custom_pipelines,
+            shaders: shaders.id(),
+            composite_pipeline,
+            mask_pipeline,
+            composite_mask_pipeline,
+            window_bind_group,
+            ping_pong_bind_groups,
+            kawase_bind_groups,
+            format,
+            copy_bind_group_layout,
+            copy_pipeline_layout,
+            stage_bind_group_layout,
+            stage_pipeline_layout,
         }
     }
 }
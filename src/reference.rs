@@ -0,0 +1,271 @@
+//! CPU implementation of the gaussian blur and the colour filters, to check
+//! what the GPU renders against.
+//!
+//! It follows `blur_rect.wgsl`, `backdrop.wgsl` and the passes around them
+//! step by step: the colours are blurred in linear space, with the kernel cut
+//! off and normalized the same way, the texture edges mirrored like the
+//! sampler does, and every pass stored at 8 bits before the next one reads
+//! it.
+
+use egui::{
+    ecolor::{
+        gamma_u8_from_linear_f32, linear_f32_from_gamma_u8, linear_f32_from_linear_u8,
+        linear_u8_from_linear_f32,
+    },
+    Rect, Rgba, Rounding,
+};
+use image::RgbaImage;
+
+use crate::{
+    backdrop_filter::{self, BackdropFilter, ColorStage, CompiledBackdrop, Stage},
+    pipeline::BlurParams,
+};
+
+/// Normalized weights of the taps `0..=n` on either side of a pixel, for a
+/// gaussian reaching `radius` physical pixels at three sigma.
+pub fn kernel(radius: f32) -> Vec<f32> {
+    let sigma = radius / 3.0;
+    let k = (2.0 * sigma * sigma).max(1e-6);
+
+    // the shader merges the taps pairwise, so it always ends on an even one
+    let size = (sigma * 3.0).ceil() as usize;
+    let taps = size.div_ceil(2) * 2;
+
+    let weights: Vec<f32> = (0..=taps).map(|i| (-((i * i) as f32) / k).exp()).collect();
+    let total = weights[0] + 2.0 * weights[1..].iter().sum::<f32>();
+
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+/// Blurs `pixels`, the rows of a `width` by `height` image in linear space,
+/// along one axis.
+fn blur_axis(
+    pixels: &[[f32; 3]],
+    width: usize,
+    height: usize,
+    kernel: &[f32],
+    horizontal: bool,
+) -> Vec<[f32; 3]> {
+    // mirrors coordinates past the edges, like `AddressMode::MirrorRepeat`
+    let mirror = |i: isize, len: usize| -> usize {
+        let len = len as isize;
+        let i = i.rem_euclid(2 * len);
+
+        (if i < len { i } else { 2 * len - 1 - i }) as usize
+    };
+
+    let mut out = vec![[0.0; 3]; pixels.len()];
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut rgb = [0.0; 3];
+
+            for (offset, weight) in kernel.iter().enumerate() {
+                let offsets: &[isize] = if offset == 0 {
+                    &[0]
+                } else {
+                    &[offset as isize, -(offset as isize)]
+                };
+
+                for &offset in offsets {
+                    let (sx, sy) = if horizontal {
+                        (mirror(x as isize + offset, width), y)
+                    } else {
+                        (x, mirror(y as isize + offset, height))
+                    };
+
+                    let sample = pixels[sy * width + sx];
+
+                    for c in 0..3 {
+                        rgb[c] += weight * sample[c];
+                    }
+                }
+            }
+
+            out[y * width + x] = rgb;
+        }
+    }
+
+    out
+}
+
+fn to_linear(image: &RgbaImage) -> Vec<[f32; 3]> {
+    image
+        .pixels()
+        .map(|p| [p.0[0], p.0[1], p.0[2]].map(linear_f32_from_gamma_u8))
+        .collect()
+}
+
+/// Rounds linear colours to 8 bit sRGB and back, like storing them in an
+/// sRGB texture does.
+fn quantize(pixels: &mut [[f32; 3]]) {
+    for rgb in pixels {
+        *rgb = rgb.map(|c| linear_f32_from_gamma_u8(gamma_u8_from_linear_f32(c)));
+    }
+}
+
+/// Both passes over all of `pixels`, `width` wide, reaching `radius`
+/// physical pixels.
+fn blur_linear(pixels: &[[f32; 3]], width: usize, radius: f32) -> Vec<[f32; 3]> {
+    let height = pixels.len() / width;
+    let kernel = kernel(radius);
+
+    let mut pixels = blur_axis(pixels, width, height, &kernel, true);
+    quantize(&mut pixels);
+
+    blur_axis(&pixels, width, height, &kernel, false)
+}
+
+/// Blurs all of `image`, reaching `radius` physical pixels.
+pub fn gaussian_blur(image: &RgbaImage, radius: f32) -> RgbaImage {
+    let pixels = blur_linear(&to_linear(image), image.width() as usize, radius);
+    let mut out = image.clone();
+
+    for (pixel, rgb) in out.pixels_mut().zip(pixels) {
+        pixel.0[..3].copy_from_slice(&rgb.map(gamma_u8_from_linear_f32));
+    }
+
+    out
+}
+
+/// Same as `gamma_from_linear` in the shaders, without rounding to 8 bits.
+fn gamma_from_linear(c: f32) -> f32 {
+    if c < 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn linear_from_gamma(c: f32) -> f32 {
+    if c < 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Same as `hash` in `backdrop.wgsl`.
+fn hash(x: u32, y: u32) -> f32 {
+    let state = x
+        .wrapping_add(y.wrapping_mul(65521))
+        .wrapping_mul(747796405)
+        .wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+
+    (((word >> 22) ^ word) >> 8) as f32 / 16777216.0
+}
+
+/// Same as `fs_color`, over all of `pixels`, `width` wide.
+fn filter_colors(pixels: &mut [[f32; 3]], width: usize, stage: &ColorStage) {
+    let tint = Rgba::from(stage.tint);
+    let tint = [tint.r(), tint.g(), tint.b()];
+
+    for (i, rgb) in pixels.iter_mut().enumerate() {
+        let gamma = rgb.map(|c| gamma_from_linear(c.max(0.0)));
+        let filtered = stage
+            .color_matrix
+            .apply(gamma)
+            .map(|c| linear_from_gamma(c.clamp(0.0, 1.0)));
+
+        for c in 0..3 {
+            rgb[c] = filtered[c] + (tint[c] - filtered[c]) * stage.tint_strength;
+        }
+
+        if stage.noise > 0.0 {
+            let grain = (hash((i % width) as u32, (i / width) as u32) - 0.5) * stage.noise;
+
+            *rgb = rgb.map(|c| linear_from_gamma((gamma_from_linear(c) + grain).clamp(0.0, 1.0)));
+        }
+    }
+}
+
+/// Same as `rounded_rect_distance` in the shaders.
+fn rounded_rect_distance(pos: egui::Pos2, rect: Rect, rounding: Rounding) -> f32 {
+    let half_size = rect.size() / 2.0;
+    let p = pos - rect.center();
+
+    let top = if p.x > 0.0 { rounding.ne } else { rounding.nw };
+    let bottom = if p.x > 0.0 { rounding.se } else { rounding.sw };
+    let rounding = if p.y > 0.0 { bottom } else { top }.min(half_size.min_elem());
+
+    let q = p.abs() - half_size + egui::Vec2::splat(rounding);
+
+    q.max_elem().min(0.0) + q.max(egui::Vec2::ZERO).length() - rounding
+}
+
+/// What [`blur_callback`](crate::blur_callback) paints over `image` with
+/// `params`, see [`backdrop`].
+pub fn blur_region(
+    image: &RgbaImage,
+    rect: Rect,
+    rounding: Rounding,
+    params: &BlurParams,
+    pixels_per_point: f32,
+) -> RgbaImage {
+    backdrop(image, rect, rounding, &Vec::from(*params), pixels_per_point)
+}
+
+/// What [`blur_callback`](crate::blur_callback) paints over `image` with
+/// `filters`, taking every [`BackdropFilter::KawaseBlur`] for a
+/// [`BackdropFilter::Blur`] and leaving out [`BackdropFilter::Custom`]
+/// shaders. `rect` and `rounding` are in points.
+pub fn backdrop(
+    image: &RgbaImage,
+    rect: Rect,
+    rounding: Rounding,
+    filters: &[BackdropFilter],
+    pixels_per_point: f32,
+) -> RgbaImage {
+    let CompiledBackdrop { stages, opacity } = backdrop_filter::compile(filters);
+    let width = image.width() as usize;
+
+    // every stage is stored to an sRGB ping-pong texture
+    let mut filtered = to_linear(image);
+
+    for stage in &stages {
+        match stage {
+            Stage::Blur { radius, .. } => {
+                filtered = blur_linear(&filtered, width, radius * pixels_per_point)
+            }
+            Stage::Color(stage) => filter_colors(&mut filtered, width, stage),
+            Stage::Custom { .. } => {}
+        }
+        quantize(&mut filtered);
+    }
+
+    if stages.is_empty() {
+        return image.clone();
+    }
+
+    let mut out = image.clone();
+
+    for (x, y, pixel) in out.enumerate_pixels_mut() {
+        // the quad covers the pixels with their centre in the rect
+        let pos = egui::pos2(
+            (x as f32 + 0.5) / pixels_per_point,
+            (y as f32 + 0.5) / pixels_per_point,
+        );
+
+        if pos.x < rect.min.x || pos.x >= rect.max.x || pos.y < rect.min.y || pos.y >= rect.max.y {
+            continue;
+        }
+
+        let coverage =
+            (0.5 - rounded_rect_distance(pos, rect, rounding) * pixels_per_point).clamp(0.0, 1.0);
+        let alpha = opacity * coverage;
+
+        let src = filtered[y as usize * width + x as usize];
+
+        for (channel, src) in pixel.0.iter_mut().zip(src) {
+            let dst = linear_f32_from_gamma_u8(*channel);
+
+            *channel = gamma_u8_from_linear_f32(src * alpha + dst * (1.0 - alpha));
+        }
+
+        let dst_alpha = linear_f32_from_linear_u8(pixel.0[3]);
+        pixel.0[3] = linear_u8_from_linear_f32(alpha + dst_alpha * (1.0 - alpha));
+    }
+
+    out
+}
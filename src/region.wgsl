@@ -0,0 +1,71 @@
+// Shared by the backdrop shaders: the uniforms of one pass of a region's
+// filter chain and the colour helpers.
+
+struct Region {
+    // part of the target the pass covers, in points: the rect grown by how
+    // far the stages after it read around their output
+    area: vec4<f32>,
+    rect: vec4<f32>,
+    // corner radii in points: nw, ne, sw, se
+    rounding: vec4<f32>,
+    // linear colour mixed in by the colour stage, by `tint.a`
+    tint: vec4<f32>,
+    // rows of the colour matrix, applied to gamma encoded colours
+    color_matrix: array<vec4<f32>, 3>,
+    radius: f32,
+    noise: f32,
+    opacity: f32,
+    pixels_per_point: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> region: Region;
+
+// Signed distance in points from `pos` to the edge of the region's rect with
+// its rounded corners, negative inside.
+fn rounded_rect_distance(pos: vec2<f32>) -> f32 {
+    let center = (region.rect.xy + region.rect.zw) / 2.0;
+    let half_size = (region.rect.zw - region.rect.xy) / 2.0;
+    let p = pos - center;
+
+    let top = select(region.rounding.x, region.rounding.y, p.x > 0.0);
+    let bottom = select(region.rounding.z, region.rounding.w, p.x > 0.0);
+    let rounding = min(select(top, bottom, p.y > 0.0), min(half_size.x, half_size.y));
+
+    let q = abs(p) - half_size + rounding;
+
+    return min(max(q.x, q.y), 0.0) + length(max(q, vec2<f32>(0.0))) - rounding;
+}
+
+// How much of the pixel at `pos` (in points) the rounded rect covers,
+// antialiased over one physical pixel.
+fn coverage(pos: vec2<f32>) -> f32 {
+    return clamp(0.5 - rounded_rect_distance(pos) * region.pixels_per_point, 0.0, 1.0);
+}
+
+fn gamma_from_linear(rgb: vec3<f32>) -> vec3<f32> {
+    let cutoff = rgb < vec3<f32>(0.0031308);
+    let lower = rgb * 12.92;
+    let higher = 1.055 * pow(rgb, vec3<f32>(1.0 / 2.4)) - 0.055;
+
+    return select(higher, lower, cutoff);
+}
+
+fn linear_from_gamma(rgb: vec3<f32>) -> vec3<f32> {
+    let cutoff = rgb < vec3<f32>(0.04045);
+    let lower = rgb / 12.92;
+    let higher = pow((rgb + 0.055) / 1.055, vec3<f32>(2.4));
+
+    return select(higher, lower, cutoff);
+}
+
+fn apply_color_matrix(rgb: vec3<f32>) -> vec3<f32> {
+    let gamma = vec4<f32>(gamma_from_linear(rgb), 1.0);
+    let filtered = vec3<f32>(
+        dot(region.color_matrix[0], gamma),
+        dot(region.color_matrix[1], gamma),
+        dot(region.color_matrix[2], gamma),
+    );
+
+    return linear_from_gamma(clamp(filtered, vec3<f32>(0.0), vec3<f32>(1.0)));
+}
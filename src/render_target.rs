@@ -0,0 +1,75 @@
+use image::RgbaImage;
+use winit::dpi::PhysicalSize;
+
+use crate::screenshot;
+
+/// What egui is eventually drawn to, a window's surface or an
+/// [`OffscreenTarget`]. The [`WindowTexture`](crate::WindowTexture) and its
+/// pipelines are built to match it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderTarget {
+    pub format: wgpu::TextureFormat,
+    pub size: PhysicalSize<u32>,
+    pub pixels_per_point: f64,
+}
+
+impl RenderTarget {
+    pub fn screen_descriptor(&self) -> egui_wgpu::renderer::ScreenDescriptor {
+        egui_wgpu::renderer::ScreenDescriptor {
+            size_in_pixels: [self.size.width, self.size.height],
+            pixels_per_point: self.pixels_per_point as f32,
+        }
+    }
+}
+
+/// A texture standing in for a window's surface, to render without a window,
+/// e.g. in tests.
+pub struct OffscreenTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    target: RenderTarget,
+}
+
+impl OffscreenTarget {
+    pub fn new(device: &wgpu::Device, target: RenderTarget) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen_target"),
+            size: wgpu::Extent3d {
+                width: target.size.width,
+                height: target.size.height,
+                ..Default::default()
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: target.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            view,
+            target,
+        }
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    pub fn target(&self) -> &RenderTarget {
+        &self.target
+    }
+
+    /// Reads back what was drawn, see [`screenshot::read_texture`].
+    pub fn read_back(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<RgbaImage> {
+        screenshot::read_texture(device, queue, &self.texture)
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::mpsc;
+
+use image::RgbaImage;
+
+/// Copies `texture` back from the GPU, blocking until it arrives. Only 8 bit
+/// RGBA and BGRA formats can be read back, `None` is returned for others and
+/// when the copy can't be mapped, e.g. once the device is lost.
+///
+/// egui writes gamma encoded colours to both sRGB and linear formats, so the
+/// bytes are taken as they are.
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Option<RgbaImage> {
+    let bgra = match texture.format() {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        _ => return None,
+    };
+
+    let (width, height) = (texture.width(), texture.height());
+
+    // rows of a texture copy have to be aligned, the padding is cut off again
+    // below
+    let unpadded_bytes_per_row = width * 4;
+    let bytes_per_row = unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("read_back"),
+        size: (bytes_per_row * height) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Read Back Encoder"),
+    });
+
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: None,
+            },
+        },
+        texture.size(),
+    );
+
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        // the receiver is still there, it's only dropped on returning
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+
+    // the callback has run once the device is done, or was dropped unrun
+    receiver.try_recv().ok()?.ok()?;
+
+    let mut pixels: Vec<u8> = slice
+        .get_mapped_range()
+        .chunks(bytes_per_row as usize)
+        .flat_map(|row| &row[..unpadded_bytes_per_row as usize])
+        .copied()
+        .collect();
+
+    if bgra {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    RgbaImage::from_raw(width, height, pixels)
+}
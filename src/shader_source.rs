@@ -0,0 +1,225 @@
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use naga::{
+    valid::{Capabilities, ValidationFlags, Validator},
+    ResourceBinding, ShaderStage,
+};
+
+/// Directory the built-in shaders are compiled in from, for
+/// [`RenderContext::watch_shaders`](crate::RenderContext::watch_shaders)
+/// while working on them.
+pub const SHADER_SOURCE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+
+/// Sources of the built-in shaders, the ones compiled in or those of a
+/// directory holding the same files.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShaderSources {
+    /// The region uniforms and colour helpers, in front of every blur
+    /// shader.
+    pub region: Cow<'static, str>,
+    /// What the shaders of stages working pixel by pixel share.
+    pub stage: Cow<'static, str>,
+    pub copy_texture: Cow<'static, str>,
+    pub blur_rect: Cow<'static, str>,
+    pub dual_kawase: Cow<'static, str>,
+    pub backdrop: Cow<'static, str>,
+}
+
+impl Default for ShaderSources {
+    fn default() -> Self {
+        Self::built_in()
+    }
+}
+
+impl ShaderSources {
+    /// Names of the files, in the order of the fields.
+    pub const FILES: [&'static str; 6] = [
+        "region.wgsl",
+        "stage.wgsl",
+        "copy_texture.wgsl",
+        "blur_rect.wgsl",
+        "dual_kawase.wgsl",
+        "backdrop.wgsl",
+    ];
+
+    /// The sources compiled into the crate.
+    pub fn built_in() -> Self {
+        Self {
+            region: Cow::Borrowed(include_str!("region.wgsl")),
+            stage: Cow::Borrowed(include_str!("stage.wgsl")),
+            copy_texture: Cow::Borrowed(include_str!("copy_texture.wgsl")),
+            blur_rect: Cow::Borrowed(include_str!("blur_rect.wgsl")),
+            dual_kawase: Cow::Borrowed(include_str!("dual_kawase.wgsl")),
+            backdrop: Cow::Borrowed(include_str!("backdrop.wgsl")),
+        }
+    }
+
+    /// Reads the [`Self::FILES`] in `dir`.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let [region, stage, copy_texture, blur_rect, dual_kawase, backdrop] =
+            Self::FILES.map(|file| fs::read_to_string(dir.join(file)).map(Cow::Owned));
+
+        Ok(Self {
+            region: region?,
+            stage: stage?,
+            copy_texture: copy_texture?,
+            blur_rect: blur_rect?,
+            dual_kawase: dual_kawase?,
+            backdrop: backdrop?,
+        })
+    }
+}
+
+/// Notices changes to the [`ShaderSources::FILES`] in a directory by polling
+/// their modification times, which is cheap enough to do every frame.
+#[derive(Debug)]
+pub(crate) struct ShaderWatcher {
+    dir: PathBuf,
+    /// Modification time and length of each file when last polled, `None`
+    /// before the first poll or while it can't be read.
+    stamps: [Option<(SystemTime, u64)>; 6],
+}
+
+impl ShaderWatcher {
+    /// A watcher of `dir`, which reports a change on its first poll.
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            stamps: [None; 6],
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether any of the files changed since the last poll.
+    pub fn poll(&mut self) -> bool {
+        let stamps = ShaderSources::FILES.map(|file| {
+            let metadata = fs::metadata(self.dir.join(file)).ok()?;
+
+            Some((metadata.modified().ok()?, metadata.len()))
+        });
+
+        // editors may save by replacing the file, leaving it missing for a
+        // moment, which isn't worth a reload
+        if stamps.contains(&None) {
+            return false;
+        }
+
+        let changed = stamps != self.stamps;
+        self.stamps = stamps;
+
+        changed
+    }
+}
+
+/// Why a shader was rejected. The messages are naga's diagnostics, with the
+/// line numbers of the shader's own source.
+#[derive(Debug)]
+pub enum ShaderError {
+    /// The sources couldn't be read from their directory.
+    Read(String),
+    Parse(String),
+    Validation(String),
+    /// An entry point the pipelines need is missing, e.g. `@fragment fn
+    /// fs_main` of a custom shader.
+    MissingEntryPoint(String),
+    /// A global is bound to a slot the pipeline doesn't fill, or the uniform
+    /// block at `@group(1) @binding(1)` is too large.
+    Binding(String),
+    /// wgpu rejected the pipelines created from the shaders, e.g. for a
+    /// global of another type than the layout has at its slot.
+    Pipeline(String),
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(message)
+            | Self::Validation(message)
+            | Self::Binding(message)
+            | Self::Pipeline(message) => f.write_str(message),
+            Self::Read(message) => write!(f, "can't read {message}"),
+            Self::MissingEntryPoint(entry_point) => write!(f, "no `{entry_point}`"),
+        }
+    }
+}
+
+impl Error for ShaderError {}
+
+/// Parses and validates `source` the way wgpu would, but returns naga's
+/// diagnostics rather than failing on the device.
+pub(crate) fn parse(label: &str, source: &str) -> Result<naga::Module, ShaderError> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|err| ShaderError::Parse(err.emit_to_string_with_path(source, label)))?;
+
+    Validator::new(ValidationFlags::all(), Capabilities::empty())
+        .validate(&module)
+        .map_err(|err| ShaderError::Validation(err.emit_to_string_with_path(source, label)))?;
+
+    Ok(module)
+}
+
+/// Checks that `module` has the entry point `name` for `stage`.
+pub(crate) fn require_entry_point(
+    module: &naga::Module,
+    stage: ShaderStage,
+    name: &str,
+) -> Result<(), ShaderError> {
+    if module
+        .entry_points
+        .iter()
+        .any(|entry_point| entry_point.name == name && entry_point.stage == stage)
+    {
+        return Ok(());
+    }
+
+    let attribute = match stage {
+        ShaderStage::Vertex => "vertex",
+        ShaderStage::Fragment => "fragment",
+        ShaderStage::Compute => "compute",
+    };
+
+    Err(ShaderError::MissingEntryPoint(format!(
+        "@{attribute} fn {name}"
+    )))
+}
+
+/// Bindings the pipelines of the stages provide, see `stage.wgsl` and
+/// `region.wgsl`.
+pub(crate) const STAGE_BINDINGS: [(u32, u32); 5] = [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1)];
+/// Bindings the pipeline copying the window texture provides.
+pub(crate) const COPY_BINDINGS: [(u32, u32); 2] = [(0, 0), (0, 1)];
+
+/// Checks that the globals of `module` are bound to distinct slots among
+/// `bindings`.
+pub(crate) fn check_bindings(
+    module: &naga::Module,
+    bindings: &[(u32, u32)],
+) -> Result<(), ShaderError> {
+    let mut bound = HashSet::new();
+
+    for (_, global) in module.global_variables.iter() {
+        let Some(ResourceBinding { group, binding }) = global.binding else {
+            continue;
+        };
+
+        if !bindings.contains(&(group, binding)) || !bound.insert((group, binding)) {
+            let name = global.name.as_deref().unwrap_or("_");
+
+            return Err(ShaderError::Binding(format!(
+                "`{name}` is bound to @group({group}) @binding({binding}), which is taken or not provided"
+            )));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+// Shared by the shaders working pixel by pixel, the colour stage, custom
+// stages and the composite: the bindings of the texture they read and the
+// quads covering the region. Custom stages declare their own uniform block
+// at `@group(1) @binding(1)`.
+
+var<private> v_positions: array<vec2<f32>, 4> = array<vec2<f32>, 4>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(-1.0, 1.0),
+    vec2<f32>(1.0, 1.0),
+);
+
+struct VertexOut {
+    @location(0) tex_coords: vec2<f32>,
+    @builtin(position) clip_position: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> screen: vec2<f32>;
+@group(0) @binding(1)
+var t_diffuse: texture_2d<f32>;
+@group(0) @binding(2)
+var s_diffuse: sampler;
+
+// covers `area` (in points) with the quad
+fn quad(area: vec4<f32>, v_idx: u32) -> VertexOut {
+    var out: VertexOut;
+    let vert = v_positions[v_idx];
+
+    out.tex_coords = vec2<f32>(
+        (vert.x + 1.0) / 2.0,
+        (-vert.y + 1.0) / 2.0,
+    );
+
+    let pos = mix(area.xy, area.zw, out.tex_coords) / screen;
+    out.clip_position = vec4<f32>(pos.x * 2.0 - 1.0, 1.0 - pos.y * 2.0, 0.0, 1.0);
+
+    return out;
+}
+
+@vertex
+fn vs_area(
+    @builtin(vertex_index) v_idx: u32
+) -> VertexOut {
+    return quad(region.area, v_idx);
+}
+
+@vertex
+fn vs_rect(
+    @builtin(vertex_index) v_idx: u32
+) -> VertexOut {
+    return quad(region.rect, v_idx);
+}
+
+// the pass reads the pixel it draws to, in the texture of the stage before
+fn load(frag_coord: vec2<f32>) -> vec3<f32> {
+    return textureLoad(t_diffuse, vec2<i32>(frag_coord), 0).rgb;
+}
+
+// PCG hash of `pixel`, uniform in [0, 1)
+fn hash(pixel: vec2<u32>) -> f32 {
+    let state = (pixel.x + pixel.y * 65521u) * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+
+    return f32(((word >> 22u) ^ word) >> 8u) / 16777216.0;
+}
@@ -1,17 +1,26 @@
+use std::{collections::HashMap, sync::Arc};
+
+use egui::{epaint::Primitive, ClippedPrimitive, Rect};
+use egui_wgpu::renderer::ScreenDescriptor;
+use image::RgbaImage;
 use winit::dpi::PhysicalSize;
 
 use crate::{
-    context::RenderContext,
-    pipeline::{PipelineRegistry, WindowPipelineRegistry},
-    surface::SurfaceRenderer,
+    backdrop_filter::BackdropFilter,
+    blur_cache::{self, BlurCache, CacheKey, RegionHash},
+    blur_callback::{self, BlurCallback, PreparedBlur, ScissorRect},
+    context::BlurShaders,
+    layers::{LayerEnd, LayerEnds},
+    pipeline::{PipelineRegistry, WindowPipelineRegistry, WindowResources, MAX_KAWASE_LEVELS},
+    render_target::RenderTarget,
+    screenshot,
 };
-pub struct WindowTexture {
-    texture: wgpu::Texture,
-    back_texture: wgpu::Texture,
-
-    texture_view: wgpu::TextureView,
-    back_texture_view: wgpu::TextureView,
 
+/// The texture egui is rendered into instead of the surface, so the blur can
+/// read back what was painted, along with the blur's own targets and
+/// pipelines. It has to be resized along with the surface.
+pub struct WindowTexture {
+    targets: Targets,
     sampler: wgpu::Sampler,
 
     pixels_per_point: f64,
@@ -19,14 +28,85 @@ pub struct WindowTexture {
 
     pipeline_registry: WindowPipelineRegistry,
     window_size_uniform: wgpu::Buffer,
+
+    /// blur callbacks of the current frame, in paint order
+    blurs: Vec<PreparedBlur>,
+    /// blur callbacks of the current frame left out, as there's no pipeline
+    /// for one of their custom shaders
+    refused: usize,
+    blur_cache: BlurCache,
 }
 
-impl WindowTexture {
-    fn create_texture(renderer: &SurfaceRenderer, device: &wgpu::Device) -> wgpu::Texture {
-        let format = renderer.format();
-        let size = renderer.size();
+/// The textures of a [`WindowTexture`] which are as large as it is.
+struct Targets {
+    texture: wgpu::Texture,
+    texture_view: wgpu::TextureView,
+
+    /// the stages of a region's filter chain draw from one into the other
+    ping_pong_textures: [wgpu::Texture; 2],
+    ping_pong_texture_views: [wgpu::TextureView; 2],
+
+    /// half, quarter, eighth, ... resolution levels of the dual kawase chain
+    kawase_textures: Vec<wgpu::Texture>,
+    kawase_texture_views: Vec<wgpu::TextureView>,
+}
+
+impl Targets {
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat, size: PhysicalSize<u32>) -> Self {
+        let texture = WindowTexture::create_texture(device, format, size);
+        let texture_view = WindowTexture::texture_view(&texture);
+
+        let ping_pong_textures =
+            [(); 2].map(|_| WindowTexture::create_texture(device, format, size));
+        let ping_pong_texture_views = ping_pong_textures
+            .each_ref()
+            .map(WindowTexture::texture_view);
+
+        let kawase_textures: Vec<_> = (1..=MAX_KAWASE_LEVELS)
+            .map(|level| {
+                let size =
+                    PhysicalSize::new((size.width >> level).max(1), (size.height >> level).max(1));
+
+                WindowTexture::create_texture(device, format, size)
+            })
+            .collect();
+        let kawase_texture_views = kawase_textures
+            .iter()
+            .map(WindowTexture::texture_view)
+            .collect();
+
+        Self {
+            texture,
+            texture_view,
+            ping_pong_textures,
+            ping_pong_texture_views,
+            kawase_textures,
+            kawase_texture_views,
+        }
+    }
+
+    fn resources<'a>(
+        &'a self,
+        sampler: &'a wgpu::Sampler,
+        window_size_uniform: &'a wgpu::Buffer,
+    ) -> WindowResources<'a> {
+        WindowResources {
+            view: &self.texture_view,
+            ping_pong_views: &self.ping_pong_texture_views,
+            kawase_views: &self.kawase_texture_views,
+            sampler,
+            window_size_uniform,
+        }
+    }
+}
 
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
+impl WindowTexture {
+    fn create_texture(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: PhysicalSize<u32>,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
             label: None,
             size: wgpu::Extent3d {
                 width: size.width,
@@ -37,11 +117,12 @@ impl WindowTexture {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
-        });
-
-        texture
+        })
     }
 
     fn create_sampler(device: &wgpu::Device) -> wgpu::Sampler {
@@ -49,64 +130,397 @@ impl WindowTexture {
             address_mode_u: wgpu::AddressMode::MirrorRepeat,
             address_mode_v: wgpu::AddressMode::MirrorRepeat,
             address_mode_w: wgpu::AddressMode::MirrorRepeat,
+            // the gaussian's taps and the kawase chain's fall between texels
+            // and rely on interpolating them. They're sampled at level 0,
+            // which some backends count as minification, so both filters
+            // have to be linear.
             mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
             mipmap_filter: wgpu::FilterMode::Nearest,
             ..Default::default()
         })
     }
 
-    pub(super) fn from_surface(renderer: &SurfaceRenderer, render_ctx: &RenderContext) -> Self {
-        let (device, ..) = render_ctx.get_device();
+    /// Creates a window texture matching `target`, which it is eventually
+    /// copied to.
+    pub fn new(device: &wgpu::Device, shaders: &BlurShaders, target: &RenderTarget) -> Self {
+        let RenderTarget {
+            format,
+            size,
+            pixels_per_point,
+        } = *target;
 
-        let texture = Self::create_texture(renderer, device);
-        let texture_view = Self::texture_view(&texture);
+        let targets = Targets::new(device, format, size);
         let sampler = Self::create_sampler(device);
 
-        let back_texture = Self::create_texture(renderer, device);
-        let back_texture_view = Self::texture_view(&back_texture);
-        let back_sampler = Self::create_sampler(device);
-
         let window_size_uniform =
-            PipelineRegistry::create_window_size_buffer(render_ctx, renderer.logical_size().cast());
+            PipelineRegistry::create_window_size_buffer(device, size.to_logical(pixels_per_point));
 
         let pipeline_registry = PipelineRegistry::generate_for_window(
-            render_ctx,
-            renderer,
-            &texture_view,
-            &back_texture_view,
-            &sampler,
-            &back_sampler,
-            &window_size_uniform,
-        )
-        .into();
-
-        let pixels_per_point = renderer.get_scale_fac();
-        let size = renderer.size();
+            device,
+            shaders,
+            target,
+            &targets.resources(&sampler, &window_size_uniform),
+        );
 
         Self {
+            targets,
             sampler,
-            texture,
             pipeline_registry,
             window_size_uniform,
-            back_texture,
-            texture_view,
-            back_texture_view,
             pixels_per_point,
             size,
+            blurs: Vec::new(),
+            refused: 0,
+            blur_cache: BlurCache::default(),
         }
     }
 
+    /// Resizes the texture to match `target`, whose format must not have
+    /// changed. Only the textures and the bind groups reading them are
+    /// created again, the pipelines are kept.
+    pub fn resize(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, target: &RenderTarget) {
+        let RenderTarget {
+            format,
+            size,
+            pixels_per_point,
+        } = *target;
+
+        assert_eq!(
+            format,
+            self.pipeline_registry.format(),
+            "a window texture can't change its format, create a new one"
+        );
+
+        if (size, pixels_per_point) == (self.size, self.pixels_per_point) {
+            return;
+        }
+
+        self.targets = Targets::new(device, format, size);
+        self.blur_cache.clear();
+
+        let size_in_points = size.to_logical::<f32>(pixels_per_point);
+        queue.write_buffer(
+            &self.window_size_uniform,
+            0,
+            bytemuck::cast_slice(&[size_in_points.width, size_in_points.height]),
+        );
+
+        self.pipeline_registry.rebind(
+            device,
+            &self
+                .targets
+                .resources(&self.sampler, &self.window_size_uniform),
+        );
+
+        self.size = size;
+        self.pixels_per_point = pixels_per_point;
+    }
+
+    /// Claims the uniform slots of the blur callbacks in `paint_jobs`, to be
+    /// called before [`egui_wgpu::Renderer::update_buffers`] with the same
+    /// paint jobs.
+    ///
+    /// A region whose backdrop, rect and filters are the same as in the
+    /// frame before reuses its filtered backdrop rather than running its
+    /// stages again. The backdrop is told by the meshes painted under the
+    /// region, so whenever the textures they are painted with change, call
+    /// [`Self::invalidate_cache`] first.
+    ///
+    /// Regions of [`layer_blur_callback`]s are moved to the bottom of their
+    /// layers, as told by the marks of [`mark_layers`].
+    ///
+    /// The blur callbacks and the marks are swapped for empty
+    /// [`egui_wgpu::CallbackFn`]s, as egui-wgpu would complain about
+    /// callbacks it can't run.
+    ///
+    /// [`layer_blur_callback`]: crate::layer_blur_callback
+    /// [`mark_layers`]: crate::mark_layers
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        paint_jobs: &mut [ClippedPrimitive],
+    ) {
+        self.pipeline_registry.regions.clear();
+        self.pipeline_registry.masks.clear();
+        self.blurs.clear();
+        self.refused = 0;
+        self.blur_cache.begin_frame();
+
+        let pixels_per_point = self.pixels_per_point as f32;
+        let layer_ends = LayerEnds::find(paint_jobs);
+        let mut region_hashes = HashMap::new();
+
+        for (
+            job,
+            ClippedPrimitive {
+                clip_rect,
+                primitive,
+            },
+        ) in paint_jobs.iter().enumerate()
+        {
+            let Primitive::Callback(callback) = primitive else {
+                continue;
+            };
+
+            let Some(blur) = callback.callback.downcast_ref::<BlurCallback>() else {
+                continue;
+            };
+
+            let Some(scissor) =
+                ScissorRect::new(blur.rect, *clip_rect, pixels_per_point, self.size)
+            else {
+                continue;
+            };
+
+            // rather than painting the region without them, see
+            // `Self::refused_regions`
+            let runs = blur.filters.iter().all(|filter| match filter {
+                BackdropFilter::Custom(shader, _) => self.pipeline_registry.runs(shader),
+                _ => true,
+            });
+            if !runs {
+                self.refused += 1;
+                continue;
+            }
+
+            let mask = blur.tessellate_mask(*clip_rect, pixels_per_point);
+            if mask.as_ref().is_some_and(|mesh| mesh.indices.is_empty()) {
+                continue;
+            }
+
+            let mask_hash = mask.as_ref().map(blur_cache::mask_hash);
+
+            let prepared = blur.prepare(
+                device,
+                queue,
+                encoder,
+                &mut self.pipeline_registry.regions,
+                *clip_rect,
+                pixels_per_point,
+            );
+
+            let Some((stages, composite, backdrop)) = prepared else {
+                continue;
+            };
+            let mask = mask.map(|mesh| {
+                self.pipeline_registry
+                    .masks
+                    .push(device, queue, encoder, &mesh)
+            });
+
+            // a region of a layer is painted at its bottom, on what the
+            // layers below paint
+            let painted_at = blur.layer.map_or(job, |layer| layer_ends.start(job, layer));
+
+            let key = CacheKey {
+                rect: blur.rect,
+                clip_rect: *clip_rect,
+                rounding: blur.rounding,
+                filters: blur.filters.clone(),
+                pixels_per_point,
+            };
+            let backdrop =
+                blur_cache::backdrop_hash(&paint_jobs[..painted_at], &region_hashes, backdrop);
+            region_hashes.insert(
+                job,
+                RegionHash {
+                    mask: mask_hash,
+                    backdrop,
+                },
+            );
+            let cache = self.blur_cache.claim(
+                device,
+                self.pipeline_registry.format(),
+                key,
+                backdrop,
+                &scissor,
+            );
+
+            self.blurs.push(PreparedBlur {
+                job: painted_at,
+                scissor,
+                stages,
+                composite,
+                cache,
+                mask,
+            });
+        }
+
+        // regions moved to the bottom of their layers are painted before
+        // the ones painted there already
+        self.blurs.sort_by_key(|blur| blur.job);
+
+        // only now, the backdrops above had to tell the blurs below apart.
+        // Without a rect egui-wgpu skips them while rendering
+        for ClippedPrimitive { primitive, .. } in paint_jobs {
+            if let Primitive::Callback(callback) = primitive {
+                if callback.callback.is::<BlurCallback>() || callback.callback.is::<LayerEnd>() {
+                    callback.callback = Arc::new(egui_wgpu::CallbackFn::new());
+                    callback.rect = Rect::NOTHING;
+                }
+            }
+        }
+    }
+
+    /// Drops the filtered backdrops kept from the frames before, which
+    /// [`Self::prepare`] can't tell are outdated when the textures egui
+    /// paints with change.
+    pub fn invalidate_cache(&mut self) {
+        self.blur_cache.clear();
+    }
+
+    /// How many regions of the frame prepared last reuse their filtered
+    /// backdrop.
+    pub fn cached_regions(&self) -> usize {
+        self.blurs.iter().filter(|blur| blur.cache.hit).count()
+    }
+
+    /// How many regions of the frame prepared last are left out, as one of
+    /// their custom shaders is of other [`BlurShaders`] or was registered
+    /// after the pipelines were created, until [`Self::reload_pipelines`].
+    pub fn refused_regions(&self) -> usize {
+        self.refused
+    }
+
+    /// Renders `paint_jobs` into the texture, blurring the backdrop of every
+    /// blur callback found by [`Self::prepare`] along the way.
+    ///
+    /// egui is rendered in one pass per run of paint jobs between two blurred
+    /// regions, so the blur passes can be recorded into `encoder` in
+    /// between. `paint_jobs` is left as it was.
+    pub fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        egui_renderer: &egui_wgpu::Renderer,
+        paint_jobs: &mut [ClippedPrimitive],
+        screen_descriptor: &ScreenDescriptor,
+    ) {
+        let clip_rects: Vec<Rect> = paint_jobs.iter().map(|job| job.clip_rect).collect();
+        let mut start = 0;
+
+        // the regions painted before the same job share their backdrop
+        let groups = self.blurs.chunk_by(|a, b| a.job == b.job);
+
+        for blurs in groups.map(Some).chain([None]) {
+            let end = blurs.map_or(paint_jobs.len(), |blurs| blurs[0].job);
+
+            // egui-wgpu skips the jobs without clip area, while still keeping
+            // track of where their vertices are, so hiding the others limits
+            // the pass to this run of jobs
+            for (idx, job) in paint_jobs.iter_mut().enumerate() {
+                job.clip_rect = if (start..end).contains(&idx) {
+                    clip_rects[idx]
+                } else {
+                    Rect::NOTHING
+                };
+            }
+
+            if start < end {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("EGUI Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &self.targets.texture_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+
+                egui_renderer.render(&mut render_pass, paint_jobs, screen_descriptor);
+            }
+
+            if let Some(blurs) = blurs {
+                blur_callback::paint(encoder, self, blurs);
+                start = end;
+            }
+        }
+
+        for (job, clip_rect) in paint_jobs.iter_mut().zip(clip_rects) {
+            job.clip_rect = clip_rect;
+        }
+    }
+
+    /// Draws the texture, with egui and the blur rendered into it, into
+    /// `render_pass`, which would usually target the surface.
+    pub fn copy_to<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let WindowPipelineRegistry {
+            copy_pipeline,
+            copy_bind_group,
+            ..
+        } = &self.pipeline_registry;
+
+        render_pass.set_pipeline(copy_pipeline);
+        render_pass.set_bind_group(0, copy_bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+    }
+
+    /// Reads back the frame rendered last, egui and the blur composited as
+    /// they are copied to the surface, e.g. to save a screenshot. The frame's
+    /// commands have to be submitted before. See
+    /// [`screenshot::read_texture`] for the formats supported.
+    pub fn read_back(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<RgbaImage> {
+        screenshot::read_texture(device, queue, &self.targets.texture)
+    }
+
+    /// Creates the pipelines again after
+    /// [`RenderContext::reload_shaders`](crate::RenderContext::reload_shaders)
+    /// or [`BlurShaders::reload`]. Should wgpu reject them, its error is
+    /// returned and the pipelines before stay in use.
+    pub fn reload_pipelines(
+        &mut self,
+        device: &wgpu::Device,
+        shaders: &BlurShaders,
+    ) -> Result<(), wgpu::Error> {
+        self.pipeline_registry.reload_pipelines(device, shaders)?;
+        self.blur_cache.clear();
+
+        Ok(())
+    }
+
     pub fn pipeline_registry(&self) -> &WindowPipelineRegistry {
         &self.pipeline_registry
     }
 
+    pub fn pipeline_registry_mut(&mut self) -> &mut WindowPipelineRegistry {
+        &mut self.pipeline_registry
+    }
+
+    pub(crate) fn blur_cache(&self) -> &BlurCache {
+        &self.blur_cache
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.targets.texture
+    }
+
+    /// The ping-pong texture `i`, `0` or `1`.
+    pub fn ping_pong_texture(&self, i: usize) -> &wgpu::Texture {
+        &self.targets.ping_pong_textures[i]
+    }
+
     pub fn view(&self) -> &wgpu::TextureView {
-        &self.texture_view
+        &self.targets.texture_view
+    }
+
+    /// View of the ping-pong texture `i`, `0` or `1`.
+    pub fn ping_pong_view(&self, i: usize) -> &wgpu::TextureView {
+        &self.targets.ping_pong_texture_views[i]
+    }
+
+    /// The dual kawase level `level`, `0` being half resolution.
+    pub fn kawase_texture(&self, level: usize) -> &wgpu::Texture {
+        &self.targets.kawase_textures[level]
     }
 
-    pub fn back_view(&self) -> &wgpu::TextureView {
-        &self.back_texture_view
+    /// View of the dual kawase level `level`, `0` being half resolution.
+    pub fn kawase_view(&self, level: usize) -> &wgpu::TextureView {
+        &self.targets.kawase_texture_views[level]
     }
 
     fn texture_view(texture: &wgpu::Texture) -> wgpu::TextureView {
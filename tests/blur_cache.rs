@@ -0,0 +1,137 @@
+mod common;
+
+use common::{assert_similar, paint_backdrop, Harness};
+use egui::{pos2, vec2, Color32, Pos2, Rect, Rounding, Shape};
+use egui_blur::{blur_callback, BackdropFilter};
+
+/// The backdrop, a square at `square` on a mesh of its own, and two
+/// overlapping regions over it.
+fn scene(square: Pos2) -> impl FnMut(&egui::Context) {
+    move |ctx| {
+        paint_backdrop(ctx);
+
+        let painter = ctx.layer_painter(egui::LayerId::background());
+        let square = Rect::from_min_size(square, vec2(20.0, 20.0));
+
+        painter
+            .with_clip_rect(square)
+            .rect_filled(square, 0.0, Color32::YELLOW);
+        painter.add(Shape::Callback(blur_callback(
+            Rect::from_min_max(pos2(20.0, 20.0), pos2(90.0, 70.0)),
+            Rounding::same(8.0),
+            [BackdropFilter::Blur(6.0)],
+        )));
+        painter.add(Shape::Callback(blur_callback(
+            Rect::from_min_max(pos2(60.0, 40.0), pos2(130.0, 90.0)),
+            Rounding::same(8.0),
+            [
+                BackdropFilter::KawaseBlur(8.0),
+                BackdropFilter::Saturate(1.4),
+            ],
+        )));
+    }
+}
+
+#[test]
+fn static_backdrops_are_cached() {
+    let mut harness = Harness::new(200, 140);
+
+    let first = harness.run(scene(pos2(160.0, 110.0)));
+    assert_eq!(harness.window_texture().cached_regions(), 2);
+
+    // changes the regions don't read leave them cached
+    let moved = harness.run_frame(scene(pos2(165.0, 115.0)));
+    assert_eq!(harness.window_texture().cached_regions(), 2);
+
+    let unmoved = Rect::from_min_max(pos2(0.0, 0.0), pos2(150.0, 140.0));
+    for (x, y, pixel) in moved.enumerate_pixels() {
+        if unmoved.contains(pos2(x as f32, y as f32)) {
+            assert_eq!(pixel, first.get_pixel(x, y), "{x}, {y}");
+        }
+    }
+
+    // the square moving under them filters both again
+    let changed = harness.run_frame(scene(pos2(70.0, 50.0)));
+    assert_eq!(harness.window_texture().cached_regions(), 0);
+
+    let cached = harness.run_frame(scene(pos2(70.0, 50.0)));
+    assert_eq!(harness.window_texture().cached_regions(), 2);
+    assert_similar("blur_cache_hit", &cached, &changed, 0);
+
+    harness.window_texture_mut().invalidate_cache();
+    let uncached = harness.run_frame(scene(pos2(70.0, 50.0)));
+    assert_eq!(harness.window_texture().cached_regions(), 0);
+    assert_similar("blur_cache_miss", &uncached, &changed, 0);
+}
+
+#[test]
+fn shapes_sharing_the_backdrops_mesh_are_told_apart() {
+    let mut harness = Harness::new(200, 140);
+    let region = Rect::from_min_max(pos2(20.0, 20.0), pos2(90.0, 70.0));
+
+    // painted on the layer and clip rect of the backdrop, the square ends up
+    // in its mesh
+    let mut scene = |square: Pos2| {
+        harness.run_frame(move |ctx| {
+            paint_backdrop(ctx);
+
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            painter.rect_filled(
+                Rect::from_min_size(square, vec2(20.0, 20.0)),
+                0.0,
+                Color32::YELLOW,
+            );
+            painter.add(Shape::Callback(blur_callback(
+                region,
+                Rounding::same(8.0),
+                [BackdropFilter::Blur(6.0)],
+            )));
+        });
+        harness.window_texture().cached_regions()
+    };
+
+    scene(pos2(160.0, 110.0));
+    assert_eq!(scene(pos2(160.0, 110.0)), 1);
+    assert_eq!(scene(pos2(165.0, 115.0)), 1, "moved away from the region");
+    assert_eq!(scene(pos2(40.0, 30.0)), 0, "moved under the region");
+    assert_eq!(scene(pos2(40.0, 30.0)), 1);
+}
+
+#[test]
+fn regions_over_regions_follow_the_backdrop_below_them() {
+    // the lower region's blur reaches far past its rect into the upper one,
+    // where the square moves under the lower region only
+    let scene = |square: Pos2| {
+        move |ctx: &egui::Context| {
+            paint_backdrop(ctx);
+
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            let square = Rect::from_min_size(square, vec2(20.0, 20.0));
+
+            painter
+                .with_clip_rect(square)
+                .rect_filled(square, 0.0, Color32::YELLOW);
+            painter.add(Shape::Callback(blur_callback(
+                Rect::from_min_max(pos2(20.0, 20.0), pos2(150.0, 110.0)),
+                Rounding::none(),
+                [BackdropFilter::Blur(30.0)],
+            )));
+            painter.add(Shape::Callback(blur_callback(
+                Rect::from_min_max(pos2(120.0, 20.0), pos2(190.0, 110.0)),
+                Rounding::none(),
+                [BackdropFilter::Blur(2.0)],
+            )));
+        }
+    };
+
+    let mut harness = Harness::new(200, 140);
+    harness.run(scene(pos2(40.0, 50.0)));
+    assert_eq!(harness.window_texture().cached_regions(), 2);
+
+    let moved = harness.run_frame(scene(pos2(90.0, 50.0)));
+    assert_eq!(harness.window_texture().cached_regions(), 0);
+
+    harness.window_texture_mut().invalidate_cache();
+    let uncached = harness.run_frame(scene(pos2(90.0, 50.0)));
+    assert_similar("blur_cache_overlap", &moved, &uncached, 0);
+}
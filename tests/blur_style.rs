@@ -0,0 +1,239 @@
+mod common;
+
+use std::cell::Cell;
+
+use common::{assert_frosted, assert_similar, paint_backdrop, Harness};
+use egui::{
+    epaint::Shadow, pos2, vec2, Area, Color32, Event, Frame, Id, Modifiers, PointerButton, Rect,
+    Ui, Window,
+};
+use egui_blur::{blur_frame, mark_layers, set_blur_style, BackdropFilter, BlurStyle, BlurWindow};
+
+fn blurred_everywhere() -> BlurStyle {
+    let blur = Some(vec![BackdropFilter::Blur(8.0)]);
+
+    BlurStyle {
+        window: blur.clone(),
+        popup: blur.clone(),
+        menu: blur.clone(),
+        tooltip: blur,
+    }
+}
+
+/// Space the contents of a frame take, with nothing painted in it.
+fn contents(ui: &mut Ui, rect: &Cell<Rect>) {
+    let (_, allocated) = ui.allocate_space(vec2(120.0, 50.0));
+    rect.set(allocated);
+}
+
+/// A window, its frame set to `filters` with [`blur_frame`] if given,
+/// returning its rect.
+fn window(
+    ctx: &egui::Context,
+    frame: Option<Frame>,
+    filters: Option<Option<Vec<BackdropFilter>>>,
+    rect: &Cell<Rect>,
+) -> Rect {
+    let mut window = Window::new("window")
+        .fixed_pos(pos2(10.0, 10.0))
+        .fixed_size(vec2(140.0, 80.0));
+    if let Some(frame) = frame {
+        window = window.frame(frame);
+    }
+
+    let response = window.show(ctx, |ui| {
+        if let Some(filters) = filters.clone() {
+            blur_frame(ui.ctx(), ui.layer_id(), filters);
+        }
+        contents(ui, rect);
+    });
+
+    response.unwrap().response.rect
+}
+
+#[test]
+fn frames_of_egui_are_blurred() {
+    let mut harness = Harness::new(360, 240);
+    harness.blur_frames();
+    let [window_rect, popup, menu, tooltip] = [(); 4].map(|()| Cell::new(Rect::NOTHING));
+    let menu_button = Cell::new(Rect::NOTHING);
+
+    let mut ui = |ctx: &egui::Context| {
+        set_blur_style(ctx, blurred_everywhere());
+        paint_backdrop(ctx);
+
+        window(ctx, None, None, &window_rect);
+
+        Area::new("buttons")
+            .fixed_pos(pos2(180.0, 10.0))
+            .show(ctx, |ui| {
+                let popup_id = Id::new("popup");
+                let response = ui.button("popup");
+                ui.memory_mut(|memory| memory.open_popup(popup_id));
+                egui::popup_below_widget(ui, popup_id, &response, |ui| contents(ui, &popup));
+
+                ui.add_space(70.0);
+                let response = ui.menu_button("menu", |ui| contents(ui, &menu));
+                menu_button.set(response.response.rect);
+            });
+
+        egui::show_tooltip_at(ctx, Id::new("tooltip"), Some(pos2(10.0, 150.0)), |ui| {
+            contents(ui, &tooltip)
+        });
+
+        mark_layers(ctx);
+    };
+
+    harness.run(&mut ui);
+    let pos = menu_button.get().center();
+    harness.push_events([true, false].map(|pressed| Event::PointerButton {
+        pos,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers: Modifiers::NONE,
+    }));
+    let image = harness.run(&mut ui);
+
+    assert_frosted("window", &image, window_rect.get());
+    assert_frosted("popup", &image, popup.get());
+    assert_frosted("menu", &image, menu.get());
+    assert_frosted("tooltip", &image, tooltip.get());
+}
+
+#[test]
+fn frames_are_left_as_egui_paints_them_without_a_style() {
+    let mut harness = Harness::new(180, 120);
+    harness.blur_frames();
+    let rect = Cell::new(Rect::NOTHING);
+
+    let mut run = |style: Option<BlurStyle>, filters: Option<Option<Vec<BackdropFilter>>>| {
+        harness.run(|ctx| {
+            set_blur_style(ctx, style.clone());
+            paint_backdrop(ctx);
+            window(ctx, None, filters.clone(), &rect);
+            mark_layers(ctx);
+        })
+    };
+
+    let unstyled = run(None, None);
+    let without_windows = run(
+        Some(BlurStyle {
+            window: None,
+            ..blurred_everywhere()
+        }),
+        None,
+    );
+    assert_similar("style_without_windows", &without_windows, &unstyled, 0);
+
+    let opted_out = run(Some(blurred_everywhere()), Some(None));
+    assert_similar("style_opted_out", &opted_out, &unstyled, 0);
+
+    let styled = run(Some(blurred_everywhere()), None);
+    assert_frosted("window", &styled, rect.get());
+
+    let reset = run(None, None);
+    assert_similar("style_reset", &reset, &unstyled, 0);
+
+    // a layer's own material needs no style
+    let set = run(None, Some(Some(vec![BackdropFilter::Blur(8.0)])));
+    assert_frosted("window of its own", &set, rect.get());
+    assert_similar("style_of_its_own", &set, &styled, 0);
+}
+
+#[test]
+fn areas_without_a_frame_are_left_alone() {
+    let mut harness = Harness::new(180, 120);
+    harness.blur_frames();
+
+    let mut run = |style: Option<BlurStyle>| {
+        harness.run(|ctx| {
+            set_blur_style(ctx, style.clone());
+            paint_backdrop(ctx);
+
+            // shown on the windows' order, starting with a button's rect
+            Area::new("buttons")
+                .fixed_pos(pos2(20.0, 20.0))
+                .show(ctx, |ui| {
+                    let _ = ui.button("first");
+                    ui.label("and the rest");
+                });
+
+            mark_layers(ctx);
+        })
+    };
+
+    let unstyled = run(None);
+    let styled = run(Some(blurred_everywhere()));
+    assert_similar("style_area_without_frame", &styled, &unstyled, 0);
+}
+
+#[test]
+fn frames_keep_their_shadow_and_lose_any_fill() {
+    let mut harness = Harness::new(220, 160);
+    harness.blur_frames();
+    let rect = Cell::new(Rect::NOTHING);
+    let window_rect = Cell::new(Rect::NOTHING);
+
+    let mut run = |style: Option<BlurStyle>, shadow: bool| {
+        harness.run(|ctx| {
+            set_blur_style(ctx, style.clone());
+            paint_backdrop(ctx);
+
+            let mut frame = Frame::window(&ctx.style()).fill(Color32::RED);
+            if !shadow {
+                frame.shadow = Shadow::NONE;
+            }
+            window_rect.set(window(ctx, Some(frame), None, &rect));
+
+            mark_layers(ctx);
+        })
+    };
+
+    let unstyled = run(None, true);
+    let styled = run(Some(blurred_everywhere()), true);
+    assert_frosted("red window", &styled, rect.get());
+
+    // and isn't under the glass
+    let without_shadow = run(Some(blurred_everywhere()), false);
+    let inside = rect.get().center();
+    let (x, y) = (inside.x as u32, inside.y as u32);
+    assert_eq!(styled.get_pixel(x, y), without_shadow.get_pixel(x, y));
+
+    // the shadow around the window is painted as egui paints it
+    let outside = window_rect.get().right_center() + vec2(6.0, 0.0);
+    let (x, y) = (outside.x as u32, outside.y as u32);
+    assert_eq!(styled.get_pixel(x, y), unstyled.get_pixel(x, y));
+
+    drop(harness);
+    let backdrop = Harness::new(220, 160).run(paint_backdrop);
+    assert_ne!(
+        styled.get_pixel(x, y),
+        backdrop.get_pixel(x, y),
+        "no shadow"
+    );
+}
+
+#[test]
+fn blur_windows_are_blurred_once() {
+    let mut harness = Harness::new(200, 140);
+    harness.blur_frames();
+    let rect = Cell::new(Rect::NOTHING);
+
+    let mut run = |style: Option<BlurStyle>| {
+        harness.run(|ctx| {
+            set_blur_style(ctx, style.clone());
+            paint_backdrop(ctx);
+
+            BlurWindow::new("window")
+                .blur([BackdropFilter::Blur(8.0)])
+                .window(|window| window.fixed_pos(pos2(20.0, 20.0)))
+                .show(ctx, |ui| contents(ui, &rect));
+
+            mark_layers(ctx);
+        })
+    };
+
+    let unstyled = run(None);
+    let styled = run(Some(blurred_everywhere()));
+    assert_similar("style_blur_window", &styled, &unstyled, 0);
+}
@@ -0,0 +1,380 @@
+//! Renders egui frames through the blur on a software adapter and compares
+//! them against the PNGs checked in under `tests/golden`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the golden images instead.
+
+// not every test uses all of the harness
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use egui_blur::{OffscreenTarget, RenderContext, RenderTarget, WindowTexture};
+use image::RgbaImage;
+use winit::dpi::PhysicalSize;
+
+/// Largest difference of a channel between a rendered and a golden pixel
+/// which still counts as matching.
+const TOLERANCE: u8 = 8;
+/// Share of pixels allowed to differ by more than [`TOLERANCE`], to leave
+/// some room for adapters antialiasing edges differently.
+const MAX_MISMATCH_RATIO: f64 = 0.002;
+
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+pub struct Harness {
+    render_ctx: RenderContext,
+    target: OffscreenTarget,
+    window_texture: WindowTexture,
+
+    egui_ctx: egui::Context,
+    egui_wgpu_renderer: egui_wgpu::Renderer,
+    /// input for the next frame run
+    events: Vec<egui::Event>,
+    /// whether the frames rendered go through [`egui_blur::blur_frames`]
+    blur_frames: bool,
+}
+
+impl Harness {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::with_scale(width, height, 1.0)
+    }
+
+    /// A target of `width` by `height` physical pixels.
+    pub fn with_scale(width: u32, height: u32, pixels_per_point: f64) -> Self {
+        Self::with_target(RenderTarget {
+            format: FORMAT,
+            size: PhysicalSize::new(width, height),
+            pixels_per_point,
+        })
+    }
+
+    pub fn with_target(target: RenderTarget) -> Self {
+        Self::with_context(render_context(), target)
+    }
+
+    /// Renders with `render_ctx`, e.g. after registering custom shaders with
+    /// it.
+    pub fn with_context(render_ctx: RenderContext, target: RenderTarget) -> Self {
+        let device = &render_ctx.device;
+        let window_texture = WindowTexture::new(device, &render_ctx.shaders, &target);
+        let target = OffscreenTarget::new(device, target);
+        let egui_wgpu_renderer = egui_wgpu::Renderer::new(device, target.target().format, None, 1);
+
+        Self {
+            render_ctx,
+            target,
+            window_texture,
+
+            egui_ctx: egui::Context::default(),
+            egui_wgpu_renderer,
+            events: Vec::new(),
+            blur_frames: false,
+        }
+    }
+
+    /// Resizes the target and the window texture, keeping its pipelines.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let target = RenderTarget {
+            size: PhysicalSize::new(width, height),
+            ..*self.target.target()
+        };
+
+        self.target = OffscreenTarget::new(&self.render_ctx.device, target);
+        self.window_texture
+            .resize(&self.render_ctx.device, &self.render_ctx.queue, &target);
+    }
+
+    pub fn render_ctx(&self) -> &RenderContext {
+        &self.render_ctx
+    }
+
+    pub fn render_ctx_mut(&mut self) -> &mut RenderContext {
+        &mut self.render_ctx
+    }
+
+    /// Reloads the shaders watched by the render context, as an app does
+    /// once per frame in dev mode, and returns whether they were, or why they
+    /// or the pipelines weren't.
+    pub fn reload_shaders(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        if !self.render_ctx.reload_shaders()? {
+            return Ok(false);
+        }
+
+        self.window_texture
+            .reload_pipelines(&self.render_ctx.device, &self.render_ctx.shaders)?;
+
+        Ok(true)
+    }
+
+    /// Creates the window texture's pipelines again, e.g. for the custom
+    /// shaders registered since.
+    pub fn reload_pipelines(&mut self) {
+        self.window_texture
+            .reload_pipelines(&self.render_ctx.device, &self.render_ctx.shaders)
+            .unwrap();
+    }
+
+    /// Runs [`egui_blur::blur_frames`] on the frames rendered from now on, as
+    /// an app with a [`egui_blur::BlurStyle`] does.
+    pub fn blur_frames(&mut self) {
+        self.blur_frames = true;
+    }
+
+    /// Queues `events` as input of the next frame run.
+    pub fn push_events(&mut self, events: impl IntoIterator<Item = egui::Event>) {
+        self.events.extend(events);
+    }
+
+    /// Runs `ui` for a single frame and returns it, for laid out UIs.
+    pub fn run_frame(&mut self, ui: impl FnMut(&egui::Context)) -> RgbaImage {
+        let raw_input = self.raw_input();
+        let full_output = self.egui_ctx.run(raw_input, ui);
+        self.render(full_output, true);
+
+        self.read_back()
+    }
+
+    pub fn window_texture_mut(&mut self) -> &mut WindowTexture {
+        &mut self.window_texture
+    }
+
+    pub fn window_texture(&self) -> &WindowTexture {
+        &self.window_texture
+    }
+
+    /// Runs `ui` until egui has laid it out and returns the last frame.
+    pub fn run(&mut self, mut ui: impl FnMut(&egui::Context)) -> RgbaImage {
+        // windows are sized during their first frame and only shown after
+        for frame in 0..2 {
+            let raw_input = self.raw_input();
+            let full_output = self.egui_ctx.run(raw_input, &mut ui);
+
+            self.render(full_output, frame == 1);
+        }
+
+        self.read_back()
+    }
+
+    fn raw_input(&mut self) -> egui::RawInput {
+        let RenderTarget {
+            size,
+            pixels_per_point,
+            ..
+        } = *self.target.target();
+
+        egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(size.width as f32, size.height as f32) / pixels_per_point as f32,
+            )),
+            pixels_per_point: Some(pixels_per_point as f32),
+            events: std::mem::take(&mut self.events),
+            ..Default::default()
+        }
+    }
+
+    fn render(&mut self, mut full_output: egui::FullOutput, to_target: bool) {
+        let RenderContext { device, queue, .. } = &self.render_ctx;
+
+        if self.blur_frames {
+            egui_blur::blur_frames(&self.egui_ctx, &mut full_output.shapes);
+        }
+        let mut paint_jobs = self.egui_ctx.tessellate(full_output.shapes);
+        let screen_descriptor = self.target.target().screen_descriptor();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Test Encoder"),
+        });
+
+        for (id, image_delta) in &full_output.textures_delta.set {
+            self.egui_wgpu_renderer
+                .update_texture(device, queue, *id, image_delta)
+        }
+
+        // the cached backdrops may have been painted with the old textures
+        if !full_output.textures_delta.set.is_empty() {
+            self.window_texture.invalidate_cache();
+        }
+
+        self.window_texture
+            .prepare(device, queue, &mut encoder, &mut paint_jobs);
+
+        self.egui_wgpu_renderer.update_buffers(
+            device,
+            queue,
+            &mut encoder,
+            &paint_jobs,
+            &screen_descriptor,
+        );
+
+        self.window_texture.render(
+            &mut encoder,
+            &self.egui_wgpu_renderer,
+            &mut paint_jobs,
+            &screen_descriptor,
+        );
+
+        if to_target {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Test copy render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: self.target.view(),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            self.window_texture.copy_to(&mut render_pass);
+        }
+
+        queue.submit(std::iter::once(encoder.finish()));
+
+        for id in &full_output.textures_delta.free {
+            self.egui_wgpu_renderer.free_texture(id);
+        }
+    }
+
+    /// Reads back the window texture rather than the target it was copied
+    /// to.
+    pub fn read_back_window_texture(&self) -> RgbaImage {
+        self.window_texture
+            .read_back(&self.render_ctx.device, &self.render_ctx.queue)
+            .unwrap()
+    }
+
+    fn read_back(&self) -> RgbaImage {
+        self.target
+            .read_back(&self.render_ctx.device, &self.render_ctx.queue)
+            .unwrap()
+    }
+}
+
+/// A context on the software adapter.
+pub fn render_context() -> RenderContext {
+    pollster::block_on(RenderContext::with_adapter_options(
+        &wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::LowPower,
+            compatible_surface: None,
+            force_fallback_adapter: true,
+        },
+    ))
+    .expect("no software adapter, install lavapipe or llvmpipe")
+}
+
+/// Compares `image` against `tests/golden/<name>.png`.
+pub fn assert_golden(name: &str, image: &RgbaImage) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        image.save(&path).unwrap();
+        return;
+    }
+
+    let golden = image::open(&path)
+        .unwrap_or_else(|err| {
+            panic!("can't open {path:?} ({err}), run with UPDATE_GOLDEN=1 to create it")
+        })
+        .to_rgba8();
+
+    assert_similar(name, image, &golden, TOLERANCE);
+}
+
+/// Checks that all but [`MAX_MISMATCH_RATIO`] of the pixels of `image` and
+/// `expected` differ by at most `tolerance` in every channel. On a mismatch
+/// `image` is saved next to the test binaries' scratch files.
+pub fn assert_similar(name: &str, image: &RgbaImage, expected: &RgbaImage, tolerance: u8) {
+    assert_eq!(
+        image.dimensions(),
+        expected.dimensions(),
+        "{name}: size differs"
+    );
+
+    let mut max_diff = 0;
+    let mismatches = image
+        .pixels()
+        .zip(expected.pixels())
+        .filter(|(a, b)| {
+            let diff =
+                a.0.iter()
+                    .zip(b.0)
+                    .map(|(a, b)| a.abs_diff(b))
+                    .max()
+                    .unwrap();
+            max_diff = max_diff.max(diff);
+
+            diff > tolerance
+        })
+        .count();
+
+    let ratio = mismatches as f64 / (image.width() * image.height()) as f64;
+
+    if ratio > MAX_MISMATCH_RATIO {
+        let actual = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.png"));
+        image.save(&actual).unwrap();
+
+        panic!(
+            "{name}: {mismatches} pixels differ by up to {max_diff}, rendered image saved to {actual:?}"
+        );
+    }
+}
+
+/// Checks the row through the middle of `rect` shows the backdrop's stripes
+/// blurred, still changing across it but without their edges, rather than
+/// as they are or covered by a fill.
+pub fn assert_frosted(name: &str, image: &RgbaImage, rect: egui::Rect) {
+    let y = rect.center().y as u32;
+    let row: Vec<[u8; 4]> = (rect.left() as u32 + 4..rect.right() as u32 - 4)
+        .map(|x| image.get_pixel(x, y).0)
+        .collect();
+
+    let step = row
+        .windows(2)
+        .flat_map(|pair| (0..3).map(|c| pair[0][c].abs_diff(pair[1][c])))
+        .max()
+        .unwrap();
+    let spread = (0..3)
+        .map(|c| {
+            let channel = row.iter().map(|pixel| pixel[c]);
+            channel.clone().max().unwrap() - channel.min().unwrap()
+        })
+        .max()
+        .unwrap();
+
+    assert!(step < 40, "{name}: stripes left, a step of {step}");
+    assert!(spread > 40, "{name}: flat, a spread of {spread}");
+}
+
+/// Vertical stripes with a few colours, which show clearly how far the blur
+/// spreads.
+pub fn paint_backdrop(ctx: &egui::Context) {
+    const COLORS: [egui::Color32; 4] = [
+        egui::Color32::WHITE,
+        egui::Color32::BLACK,
+        egui::Color32::from_rgb(220, 40, 40),
+        egui::Color32::from_rgb(40, 80, 220),
+    ];
+
+    egui::CentralPanel::default()
+        .frame(egui::Frame::none())
+        .show(ctx, |ui| {
+            let rect = ui.max_rect();
+            let painter = ui.painter();
+
+            for (i, x) in (0..rect.width() as usize).step_by(12).enumerate() {
+                painter.rect_filled(
+                    egui::Rect::from_min_size(
+                        egui::pos2(x as f32, rect.top()),
+                        egui::vec2(12.0, rect.height()),
+                    ),
+                    0.0,
+                    COLORS[i % COLORS.len()],
+                );
+            }
+        });
+}
@@ -0,0 +1,324 @@
+mod common;
+
+use std::cell::Cell;
+
+use common::{assert_frosted, paint_backdrop, Harness};
+use egui::{
+    pos2, vec2, Area, ComboBox, Event, Id, LayerId, Modifiers, PointerButton, Pos2, Rect, Sense,
+    SidePanel, TopBottomPanel, Ui,
+};
+use egui_blur::{mark_layers, BackdropFilter, BlurArea, BlurPanel, BlurPopup};
+
+const BLUR: [BackdropFilter; 1] = [BackdropFilter::Blur(8.0)];
+
+/// Space the contents of a popup take, with nothing painted in it.
+fn contents(ui: &mut Ui, rect: &Cell<Rect>) {
+    let (_, allocated) = ui.allocate_space(vec2(120.0, 50.0));
+    rect.set(allocated);
+}
+
+fn click(pos: Pos2, button: PointerButton) -> [Event; 3] {
+    let press = |pressed| Event::PointerButton {
+        pos,
+        button,
+        pressed,
+        modifiers: Modifiers::NONE,
+    };
+
+    [Event::PointerMoved(pos), press(true), press(false)]
+}
+
+#[test]
+fn area() {
+    let mut harness = Harness::new(200, 140);
+    let rect = Cell::new(Rect::NOTHING);
+
+    let image = harness.run(|ctx| {
+        paint_backdrop(ctx);
+
+        let area = Area::new("area").fixed_pos(pos2(30.0, 30.0));
+        let response = BlurArea::new(area)
+            .blur(BLUR)
+            .show(ctx, |ui| ui.allocate_space(vec2(120.0, 60.0)));
+        rect.set(response.response.rect);
+
+        mark_layers(ctx);
+    });
+
+    assert_frosted("area", &image, rect.get());
+}
+
+#[test]
+fn panels() {
+    let mut harness = Harness::new(240, 160);
+    let top = Cell::new(Rect::NOTHING);
+    let side = Cell::new(Rect::NOTHING);
+
+    let image = harness.run(|ctx| {
+        // panels blur what's painted on the background before them, so the
+        // stripes can't be on a panel of their own
+        let painter = ctx.layer_painter(LayerId::background());
+        for x in (0..240).step_by(12) {
+            let color = if x % 24 == 0 {
+                egui::Color32::WHITE
+            } else {
+                egui::Color32::from_rgb(40, 80, 220)
+            };
+            let stripe = Rect::from_min_size(pos2(x as f32, 0.0), vec2(12.0, 160.0));
+            painter.rect_filled(stripe, 0.0, color);
+        }
+
+        let response = BlurPanel::new(TopBottomPanel::top("top").exact_height(40.0))
+            .blur(BLUR)
+            .show(ctx, |_| ());
+        top.set(response.response.rect);
+
+        let response = BlurPanel::new(SidePanel::left("side").exact_width(100.0))
+            .blur(BLUR)
+            .show(ctx, |_| ());
+        side.set(response.response.rect);
+
+        mark_layers(ctx);
+    });
+
+    assert_frosted("top_panel", &image, top.get());
+    assert_frosted("side_panel", &image, side.get());
+}
+
+#[test]
+fn popup_below_widget() {
+    let mut harness = Harness::new(200, 140);
+    harness.blur_frames();
+    let rect = Cell::new(Rect::NOTHING);
+
+    let image = harness.run(|ctx| {
+        paint_backdrop(ctx);
+
+        Area::new("button")
+            .fixed_pos(pos2(20.0, 10.0))
+            .show(ctx, |ui| {
+                let popup_id = Id::new("popup");
+                let response = ui.button("open");
+                ui.memory_mut(|memory| memory.open_popup(popup_id));
+
+                BlurPopup::new()
+                    .blur(BLUR)
+                    .below_widget(ui, popup_id, &response, |ui| contents(ui, &rect));
+            });
+
+        mark_layers(ctx);
+    });
+
+    assert_frosted("popup", &image, rect.get());
+}
+
+#[test]
+fn combo_box() {
+    let mut harness = Harness::new(200, 140);
+    harness.blur_frames();
+    let rect = Cell::new(Rect::NOTHING);
+
+    let image = harness.run(|ctx| {
+        paint_backdrop(ctx);
+
+        Area::new("combo")
+            .fixed_pos(pos2(20.0, 10.0))
+            .show(ctx, |ui| {
+                let popup_id = ui.make_persistent_id(Id::new("combo")).with("popup");
+                ui.memory_mut(|memory| memory.open_popup(popup_id));
+
+                BlurPopup::new().blur(BLUR).combo_box(
+                    ui,
+                    ComboBox::from_id_source("combo").selected_text("open"),
+                    |ui| contents(ui, &rect),
+                );
+            });
+
+        mark_layers(ctx);
+    });
+
+    assert_frosted("combo_box", &image, rect.get());
+}
+
+#[test]
+fn menus() {
+    let mut harness = Harness::new(220, 160);
+    harness.blur_frames();
+    let button = Cell::new(Rect::NOTHING);
+    let rect = Cell::new(Rect::NOTHING);
+
+    let mut ui = |ctx: &egui::Context| {
+        paint_backdrop(ctx);
+        rect.set(Rect::NOTHING);
+
+        Area::new("menu")
+            .fixed_pos(pos2(20.0, 10.0))
+            .show(ctx, |ui| {
+                let response = BlurPopup::new()
+                    .blur(BLUR)
+                    .menu_button(ui, "menu", |ui| contents(ui, &rect));
+                button.set(response.response.rect);
+            });
+
+        mark_layers(ctx);
+    };
+
+    harness.run(&mut ui);
+    assert_eq!(rect.get(), Rect::NOTHING, "open before clicked");
+
+    harness.push_events(click(button.get().center(), PointerButton::Primary));
+    let image = harness.run(&mut ui);
+
+    assert_frosted("menu", &image, rect.get());
+
+    // egui closes menus on the press
+    harness.push_events(
+        click(pos2(200.0, 140.0), PointerButton::Primary)
+            .into_iter()
+            .take(2),
+    );
+    harness.run(&mut ui);
+    assert_eq!(rect.get(), Rect::NOTHING, "open after a click elsewhere");
+}
+
+#[test]
+fn submenus() {
+    let mut harness = Harness::new(320, 160);
+    harness.blur_frames();
+    let [button, submenu_button] = [(); 2].map(|()| Cell::new(Rect::NOTHING));
+    let rect = Cell::new(Rect::NOTHING);
+
+    let mut ui = |ctx: &egui::Context| {
+        paint_backdrop(ctx);
+        rect.set(Rect::NOTHING);
+
+        Area::new("menu")
+            .fixed_pos(pos2(20.0, 10.0))
+            .show(ctx, |ui| {
+                let popup = BlurPopup::new().blur(BLUR);
+                let response = popup.menu_button(ui, "menu", |ui| {
+                    let response = popup.menu_button(ui, "submenu", |ui| contents(ui, &rect));
+                    submenu_button.set(response.response.rect);
+                });
+                button.set(response.response.rect);
+            });
+
+        mark_layers(ctx);
+    };
+
+    harness.run(&mut ui);
+    harness.push_events(click(button.get().center(), PointerButton::Primary));
+    harness.run(&mut ui);
+    assert_eq!(rect.get(), Rect::NOTHING, "open before hovered");
+
+    // as egui's submenus do
+    harness.push_events([Event::PointerMoved(submenu_button.get().center())]);
+    let image = harness.run(&mut ui);
+
+    assert_frosted("submenu", &image, rect.get());
+}
+
+#[test]
+fn popups_leave_the_style_alone() {
+    let mut harness = Harness::new(220, 160);
+    harness.blur_frames();
+    let style = egui::Style::default();
+
+    harness.run(|ctx| {
+        paint_backdrop(ctx);
+
+        Area::new("popups")
+            .fixed_pos(pos2(20.0, 10.0))
+            .show(ctx, |ui| {
+                let popup = BlurPopup::new().blur(BLUR);
+                let response = ui.button("open");
+                ui.memory_mut(|memory| memory.open_popup(Id::new("popup")));
+
+                popup.below_widget(ui, Id::new("popup"), &response, |ui| {
+                    assert_eq!(ui.style().visuals, style.visuals, "popup's style");
+                    assert_eq!(*ui.ctx().style(), style, "context's style in a popup");
+                });
+            });
+        BlurPopup::new().tooltip_at(ctx, Id::new("at"), Some(pos2(10.0, 80.0)), |ui| {
+            assert_eq!(*ui.ctx().style(), style, "context's style in a tooltip");
+        });
+
+        assert_eq!(*ctx.style(), style, "context's style after the popups");
+        mark_layers(ctx);
+    });
+}
+
+#[test]
+fn context_menu() {
+    let mut harness = Harness::new(220, 160);
+    harness.blur_frames();
+    let rect = Cell::new(Rect::NOTHING);
+
+    let mut ui = |ctx: &egui::Context| {
+        paint_backdrop(ctx);
+
+        Area::new("target").fixed_pos(Pos2::ZERO).show(ctx, |ui| {
+            let response = ui.allocate_response(vec2(220.0, 160.0), Sense::click());
+            BlurPopup::new()
+                .blur(BLUR)
+                .context_menu(response, |ui| contents(ui, &rect));
+        });
+
+        mark_layers(ctx);
+    };
+
+    // areas only take input once they've been shown
+    harness.run(&mut ui);
+    // egui opens context menus on the press
+    harness.push_events(
+        click(pos2(30.0, 30.0), PointerButton::Secondary)
+            .into_iter()
+            .take(2),
+    );
+    let image = harness.run(&mut ui);
+
+    assert_frosted("context_menu", &image, rect.get());
+}
+
+#[test]
+fn tooltips() {
+    let mut harness = Harness::new(220, 160);
+    harness.blur_frames();
+    let at = Cell::new(Rect::NOTHING);
+    let hovered = Cell::new(Rect::NOTHING);
+
+    let image = harness.run(|ctx| {
+        paint_backdrop(ctx);
+
+        BlurPopup::new()
+            .blur(BLUR)
+            .tooltip_at(ctx, Id::new("at"), Some(pos2(10.0, 10.0)), |ui| {
+                contents(ui, &at)
+            });
+
+        mark_layers(ctx);
+    });
+
+    assert_frosted("tooltip_at", &image, at.get());
+
+    let mut ui = |ctx: &egui::Context| {
+        paint_backdrop(ctx);
+
+        Area::new("hovered")
+            .fixed_pos(pos2(20.0, 10.0))
+            .show(ctx, |ui| {
+                let response = ui.allocate_response(vec2(60.0, 30.0), Sense::hover());
+                BlurPopup::new()
+                    .blur(BLUR)
+                    .on_hover_ui(response, |ui| contents(ui, &hovered));
+            });
+
+        mark_layers(ctx);
+    };
+
+    harness.run(&mut ui);
+    harness.push_events([Event::PointerMoved(pos2(40.0, 20.0))]);
+    let image = harness.run(&mut ui);
+
+    assert_frosted("on_hover_ui", &image, hovered.get());
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use common::{assert_similar, paint_backdrop, render_context, Harness, FORMAT};
+use egui::{pos2, Color32, Rect, Rgba, Rounding, Shape};
+use egui_blur::{
+    blur_callback, BackdropFilter, BlurShaders, CustomShader, CustomShaderDescriptor,
+    RenderContext, RenderTarget, ShaderError,
+};
+use winit::dpi::PhysicalSize;
+
+const INVERT: &str = "
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let gamma = gamma_from_linear(load(in.clip_position.xy));
+
+    return vec4<f32>(linear_from_gamma(1.0 - gamma), 1.0);
+}
+";
+
+const TINT: &str = "
+struct Tint {
+    color: vec4<f32>,
+    strength: f32,
+}
+
+@group(1) @binding(1)
+var<uniform> tint: Tint;
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let rgb = load(in.clip_position.xy);
+
+    return vec4<f32>(mix(rgb, tint.color.rgb, tint.strength), 1.0);
+}
+";
+
+fn descriptor(source: &str) -> CustomShaderDescriptor<'_> {
+    CustomShaderDescriptor {
+        label: "custom.wgsl",
+        source,
+        reach: 0.0,
+    }
+}
+
+fn harness(render_ctx: RenderContext) -> Harness {
+    Harness::with_context(
+        render_ctx,
+        RenderTarget {
+            format: FORMAT,
+            size: PhysicalSize::new(160, 120),
+            pixels_per_point: 1.0,
+        },
+    )
+}
+
+/// Renders the backdrop under each of `filters` in turn.
+fn render(render_ctx: RenderContext, filters: &[Vec<BackdropFilter>]) -> Vec<image::RgbaImage> {
+    let mut harness = harness(render_ctx);
+
+    filters
+        .iter()
+        .map(|filters| render_with(&mut harness, filters))
+        .collect()
+}
+
+fn render_with(harness: &mut Harness, filters: &[BackdropFilter]) -> image::RgbaImage {
+    let rect = Rect::from_min_max(pos2(20.0, 20.0), pos2(140.0, 100.0));
+
+    harness.run(|ctx| {
+        paint_backdrop(ctx);
+        ctx.layer_painter(egui::LayerId::background())
+            .add(Shape::Callback(blur_callback(
+                rect,
+                Rounding::same(12.0),
+                filters.to_vec(),
+            )));
+    })
+}
+
+/// Registers [`INVERT`] with the shaders of the harness' window texture.
+fn register_invert(harness: &mut Harness) -> CustomShader {
+    let render_ctx = harness.render_ctx_mut();
+
+    render_ctx
+        .shaders
+        .register_custom(&render_ctx.device, &descriptor(INVERT))
+        .unwrap()
+}
+
+#[test]
+fn custom_shader_matches_builtin_filter() {
+    use BackdropFilter::*;
+
+    let mut render_ctx = render_context();
+    let invert = render_ctx
+        .shaders
+        .register_custom(&render_ctx.device, &descriptor(INVERT))
+        .unwrap();
+
+    let images = render(
+        render_ctx,
+        &[
+            vec![Blur(6.0), Custom(invert, [0.0; 16])],
+            vec![Blur(6.0), Invert(1.0)],
+        ],
+    );
+
+    assert_similar("custom_invert", &images[0], &images[1], 1);
+}
+
+#[test]
+fn custom_shader_reads_its_uniforms() {
+    use BackdropFilter::*;
+
+    let mut render_ctx = render_context();
+    let tint = render_ctx
+        .shaders
+        .register_custom(&render_ctx.device, &descriptor(TINT))
+        .unwrap();
+
+    let color = Color32::from_rgb(40, 200, 120);
+    let linear = Rgba::from(color);
+    let mut params = [0.0; 16];
+    params[..5].copy_from_slice(&[linear.r(), linear.g(), linear.b(), 1.0, 0.4]);
+
+    let images = render(
+        render_ctx,
+        &[
+            vec![Blur(6.0), Custom(tint, params)],
+            vec![Blur(6.0), Tint(color, 0.4)],
+        ],
+    );
+
+    assert_similar("custom_tint", &images[0], &images[1], 1);
+}
+
+#[test]
+fn shaders_registered_later_run_once_the_pipelines_are_reloaded() {
+    use BackdropFilter::*;
+
+    let mut harness = harness(render_context());
+    let invert = register_invert(&mut harness);
+    harness.reload_pipelines();
+
+    let custom = render_with(&mut harness, &[Blur(6.0), Custom(invert, [0.0; 16])]);
+    let builtin = render_with(&mut harness, &[Blur(6.0), Invert(1.0)]);
+
+    assert_similar("custom_registered_later", &custom, &builtin, 1);
+}
+
+/// Asserts the region with `shader` is left out, the backdrop painted as is.
+fn assert_refused(harness: &mut Harness, shader: CustomShader) {
+    let refused = render_with(harness, &[BackdropFilter::Custom(shader, [0.0; 16])]);
+    assert_eq!(harness.window_texture().refused_regions(), 1);
+
+    let backdrop = harness.run(paint_backdrop);
+    assert_eq!(harness.window_texture().refused_regions(), 0);
+    assert_similar("custom_refused", &refused, &backdrop, 0);
+}
+
+#[test]
+fn shaders_registered_later_are_refused_before_reloading() {
+    let mut harness = harness(render_context());
+    let invert = register_invert(&mut harness);
+
+    assert_refused(&mut harness, invert);
+}
+
+#[test]
+fn shaders_of_other_registries_are_refused() {
+    let mut harness = harness(render_context());
+    // the same index as the harness' own shader would have
+    let device = &harness.render_ctx().device;
+    let invert = BlurShaders::new(device)
+        .register_custom(device, &descriptor(INVERT))
+        .unwrap();
+
+    register_invert(&mut harness);
+    harness.reload_pipelines();
+    assert_refused(&mut harness, invert);
+}
+
+#[test]
+fn invalid_shaders_are_rejected() {
+    let mut render_ctx = render_context();
+    let mut register = |source: &str| {
+        render_ctx
+            .shaders
+            .register_custom(&render_ctx.device, &descriptor(source))
+    };
+
+    match register("\n@fragment\nfn fs_main( -> {}") {
+        Err(ShaderError::Parse(message)) => {
+            // the diagnostic points into the shader's own source
+            assert!(message.contains("custom.wgsl:3"), "{message}")
+        }
+        other => panic!("{other:?}"),
+    }
+
+    assert!(matches!(
+        register("fn fs_main() -> f32 { return undefined; }"),
+        Err(ShaderError::Parse(_))
+    ));
+
+    assert!(matches!(
+        register("@fragment fn main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }"),
+        Err(ShaderError::MissingEntryPoint(_))
+    ));
+
+    assert!(matches!(
+        register(
+            "@group(2) @binding(0) var<uniform> extra: vec4<f32>;
+            @fragment fn fs_main() -> @location(0) vec4<f32> { return extra; }"
+        ),
+        Err(ShaderError::Binding(_))
+    ));
+
+    assert!(matches!(
+        register(
+            "@group(1) @binding(1) var<uniform> large: array<vec4<f32>, 8>;
+            @fragment fn fs_main() -> @location(0) vec4<f32> { return large[0]; }"
+        ),
+        Err(ShaderError::Binding(_))
+    ));
+
+    assert!(render_ctx.shaders.custom.is_empty());
+}
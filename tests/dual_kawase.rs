@@ -0,0 +1,74 @@
+mod common;
+
+use common::Harness;
+use egui::{pos2, Color32, Rect, Rounding, Shape};
+use egui_blur::{blur_callback, kawase_iterations, BackdropFilter};
+
+const WIDTH: u32 = 400;
+
+/// Standard deviation, in pixels, of the linear light of a 1 pixel wide
+/// line once `filter` spread it.
+fn spread(filter: BackdropFilter) -> f64 {
+    let line = WIDTH as f32 / 2.0;
+    let screen = Rect::from_min_max(pos2(0.0, 0.0), pos2(WIDTH as f32, 20.0));
+
+    let image = Harness::new(WIDTH, 20).run(|ctx| {
+        let painter = ctx.layer_painter(egui::LayerId::background());
+        painter.rect_filled(screen, 0.0, Color32::BLACK);
+        painter.rect_filled(
+            Rect::from_min_max(pos2(line, 0.0), pos2(line + 1.0, 20.0)),
+            0.0,
+            Color32::WHITE,
+        );
+        painter.add(Shape::Callback(blur_callback(
+            screen,
+            Rounding::none(),
+            [filter],
+        )));
+    });
+
+    let (mut total, mut sum, mut sum_squares) = (0.0, 0.0, 0.0);
+    for x in 0..WIDTH {
+        let light = egui::ecolor::linear_f32_from_gamma_u8(image.get_pixel(x, 10).0[0]) as f64;
+        let x = x as f64 + 0.5;
+
+        total += light;
+        sum += light * x;
+        sum_squares += light * x * x;
+    }
+
+    let mean = sum / total;
+    (sum_squares / total - mean * mean).sqrt()
+}
+
+#[test]
+fn kawase_spreads_as_far_as_the_gaussian() {
+    // the radii the iterations reach exactly, the coarser levels of larger
+    // ones are only a few pixels wide here
+    for iterations in 1..=4 {
+        let radius = 2.3 * (1 << iterations) as f32;
+        assert_eq!(kawase_iterations(radius), iterations);
+
+        let kawase = spread(BackdropFilter::KawaseBlur(radius));
+        let gaussian = spread(BackdropFilter::Blur(radius));
+
+        assert!(
+            (kawase / gaussian - 1.0).abs() < 0.15,
+            "radius {radius}: a deviation of {kawase:.2} rather than {gaussian:.2}"
+        );
+    }
+}
+
+#[test]
+fn kawase_reaches_at_least_the_radius() {
+    // between the radii the iterations reach, the next one is picked
+    for radius in [6.0, 12.0, 25.0] {
+        let kawase = spread(BackdropFilter::KawaseBlur(radius));
+        let gaussian = spread(BackdropFilter::Blur(radius));
+
+        assert!(
+            (0.9..2.1).contains(&(kawase / gaussian)),
+            "radius {radius}: a deviation of {kawase:.2} rather than {gaussian:.2}"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::{assert_golden, paint_backdrop, Harness};
+use egui::{pos2, vec2, Color32, Rect, Rounding, Shape};
+use egui_blur::{blur_callback, mark_layers, BackdropFilter, BlurMode, BlurParams, BlurWindow};
+
+fn blur_window(ctx: &egui::Context, filters: impl Into<Vec<BackdropFilter>>) {
+    BlurWindow::new("Blurred")
+        .blur(filters)
+        .window(|window| {
+            window
+                .fixed_pos(pos2(40.0, 30.0))
+                .fixed_size(vec2(140.0, 80.0))
+        })
+        .show(ctx, |ui| ui.label("frosted glass"));
+}
+
+#[test]
+fn gaussian_window() {
+    let image = Harness::new(260, 160).run(|ctx| {
+        paint_backdrop(ctx);
+        blur_window(
+            ctx,
+            BlurParams {
+                radius: 18.0,
+                ..Default::default()
+            },
+        );
+    });
+
+    assert_golden("gaussian_window", &image);
+}
+
+#[test]
+fn dual_kawase_window() {
+    let image = Harness::new(260, 160).run(|ctx| {
+        paint_backdrop(ctx);
+        blur_window(
+            ctx,
+            BlurParams {
+                mode: BlurMode::DualKawase,
+                radius: 18.0,
+                tint: Color32::from_rgb(40, 200, 120),
+                tint_strength: 0.3,
+                ..Default::default()
+            },
+        );
+    });
+
+    assert_golden("dual_kawase_window", &image);
+}
+
+#[test]
+fn clipped_rounded_blur() {
+    let image = Harness::new(200, 120).run(|ctx| {
+        paint_backdrop(ctx);
+
+        let clip_rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(120.0, 120.0));
+        let rect = Rect::from_min_max(pos2(40.0, 20.0), pos2(160.0, 100.0));
+
+        ctx.layer_painter(egui::LayerId::background())
+            .with_clip_rect(clip_rect)
+            .add(Shape::Callback(blur_callback(
+                rect,
+                Rounding::same(24.0),
+                BlurParams::default(),
+            )));
+    });
+
+    assert_golden("clipped_rounded_blur", &image);
+}
+
+#[test]
+fn filter_chain_window() {
+    use BackdropFilter::*;
+
+    let image = Harness::new(260, 160).run(|ctx| {
+        paint_backdrop(ctx);
+        blur_window(
+            ctx,
+            [
+                KawaseBlur(12.0),
+                Saturate(1.8),
+                Tint(Color32::WHITE, 0.2),
+                Noise(0.03),
+            ],
+        );
+    });
+
+    assert_golden("filter_chain_window", &image);
+}
+
+#[test]
+fn stacked_windows() {
+    let image = Harness::new(300, 220).run(|ctx| {
+        paint_backdrop(ctx);
+
+        for (title, pos, radius) in [
+            ("Bottom", pos2(20.0, 20.0), 6.0),
+            ("Middle", pos2(90.0, 60.0), 10.0),
+            ("Top", pos2(150.0, 100.0), 14.0),
+        ] {
+            BlurWindow::new(title)
+                .blur(BlurParams {
+                    radius,
+                    ..Default::default()
+                })
+                .window(|window| window.fixed_pos(pos).fixed_size(vec2(110.0, 70.0)))
+                .show(ctx, |ui| ui.label(title));
+        }
+
+        mark_layers(ctx);
+    });
+
+    assert_golden("stacked_windows", &image);
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::{assert_similar, paint_backdrop, Harness, FORMAT};
+use egui::{pos2, Rect, Rounding, Shape};
+use egui_blur::{
+    blur_callback, BackdropFilter, RenderTarget, ShaderError, ShaderSources, WindowTexture,
+    SHADER_SOURCE_DIR,
+};
+use winit::dpi::PhysicalSize;
+
+const COMPOSITE: &str =
+    "return vec4<f32>(load(in.clip_position.xy), region.opacity * coverage(pos));";
+
+const SCREEN: &str = "var<uniform> screen";
+
+/// A copy of the built-in shaders to edit, one per test.
+fn shader_dir(test: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("hot_reload")
+        .join(test);
+    fs::create_dir_all(&dir).unwrap();
+
+    for file in ShaderSources::FILES {
+        fs::copy(PathBuf::from(SHADER_SOURCE_DIR).join(file), dir.join(file)).unwrap();
+    }
+
+    dir
+}
+
+fn render(harness: &mut Harness) -> image::RgbaImage {
+    let rect = Rect::from_min_max(pos2(20.0, 20.0), pos2(140.0, 100.0));
+
+    harness.run(|ctx| {
+        paint_backdrop(ctx);
+        ctx.layer_painter(egui::LayerId::background())
+            .add(Shape::Callback(blur_callback(
+                rect,
+                Rounding::same(12.0),
+                [BackdropFilter::Blur(8.0)],
+            )));
+    })
+}
+
+#[test]
+fn shaders_reload_and_keep_the_last_good_ones() {
+    let dir = shader_dir("keep_the_last_good_ones");
+    let backdrop_path = dir.join("backdrop.wgsl");
+    let backdrop = fs::read_to_string(&backdrop_path).unwrap();
+    assert!(backdrop.contains(COMPOSITE));
+
+    let mut harness = Harness::new(160, 120);
+    let built_in = render(&mut harness);
+
+    // the copy loads on the first poll, and matches what's built in
+    harness.render_ctx_mut().watch_shaders(&dir);
+    assert!(harness.reload_shaders().unwrap());
+    assert!(!harness.reload_shaders().unwrap());
+    assert_similar("hot_reload_copy", &render(&mut harness), &built_in, 0);
+
+    // neither a shader which doesn't parse nor one the pipelines can't use
+    // replaces the ones before
+    fs::write(&backdrop_path, format!("{backdrop}\nfn broken(")).unwrap();
+    assert!(harness.reload_shaders().is_err());
+    assert_similar("hot_reload_broken", &render(&mut harness), &built_in, 0);
+
+    fs::write(
+        &backdrop_path,
+        backdrop.replace("fn fs_color", "fn fs_colour"),
+    )
+    .unwrap();
+    let err = harness.reload_shaders().unwrap_err();
+    assert!(err.to_string().contains("fs_color"), "{err}");
+    assert_similar("hot_reload_renamed", &render(&mut harness), &built_in, 0);
+
+    // a storage buffer where the layout has a uniform, which only wgpu
+    // catches once the pipelines are created
+    let stage_path = dir.join("stage.wgsl");
+    let stage = fs::read_to_string(&stage_path).unwrap();
+    assert!(stage.contains(SCREEN));
+
+    fs::write(&backdrop_path, &backdrop).unwrap();
+    fs::write(&stage_path, stage.replace(SCREEN, "var<storage> screen")).unwrap();
+    assert!(harness.reload_shaders().is_err());
+    assert_similar("hot_reload_layout", &render(&mut harness), &built_in, 0);
+    fs::write(&stage_path, &stage).unwrap();
+
+    // once fixed, the region composites solid red
+    fs::write(
+        &backdrop_path,
+        backdrop.replace(
+            COMPOSITE,
+            "return vec4<f32>(1.0, 0.0, 0.0, region.opacity * coverage(pos));",
+        ),
+    )
+    .unwrap();
+    assert!(harness.reload_shaders().unwrap());
+
+    let image = render(&mut harness);
+    assert_eq!(image.get_pixel(80, 60).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(5, 5), built_in.get_pixel(5, 5));
+}
+
+#[test]
+fn window_textures_are_created_from_the_last_good_shaders() {
+    let dir = shader_dir("window_textures");
+    let stage_path = dir.join("stage.wgsl");
+    let stage = fs::read_to_string(&stage_path).unwrap();
+    assert!(stage.contains(SCREEN));
+
+    let mut harness = Harness::new(160, 120);
+    let built_in = render(&mut harness);
+
+    // parses, but wgpu rejects the pipelines created from it
+    fs::write(&stage_path, stage.replace(SCREEN, "var<storage> screen")).unwrap();
+    harness.render_ctx_mut().watch_shaders(&dir);
+    let err = harness.reload_shaders().unwrap_err();
+    assert!(
+        matches!(err.downcast_ref(), Some(ShaderError::Pipeline(_))),
+        "{err}"
+    );
+
+    let render_ctx = harness.render_ctx();
+    let target = RenderTarget {
+        format: FORMAT,
+        size: PhysicalSize::new(160, 120),
+        pixels_per_point: 1.0,
+    };
+    let window_texture = WindowTexture::new(&render_ctx.device, &render_ctx.shaders, &target);
+    *harness.window_texture_mut() = window_texture;
+
+    assert_similar(
+        "hot_reload_new_texture",
+        &render(&mut harness),
+        &built_in,
+        0,
+    );
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use common::{assert_similar, paint_backdrop, Harness};
+use egui::{pos2, vec2, Area, Color32, Order, Pos2, Rect, Rounding, Shape};
+use egui_blur::{layer_blur_callback, mark_layers, BackdropFilter, BlurWindow};
+use image::RgbaImage;
+
+/// An area on `order` with a blurred region and a stripe over it, the region
+/// painted first or last.
+fn blurred_area(
+    ctx: &egui::Context,
+    id: &'static str,
+    order: Order,
+    rect: Rect,
+    region_first: bool,
+) {
+    Area::new(id)
+        .order(order)
+        .fixed_pos(rect.min)
+        .show(ctx, |ui| {
+            let region = Shape::Callback(layer_blur_callback(
+                ui.layer_id(),
+                rect,
+                Rounding::same(6.0),
+                [BackdropFilter::Blur(8.0)],
+            ));
+            let stripe = Shape::rect_filled(
+                Rect::from_min_size(rect.min + vec2(8.0, 8.0), vec2(rect.width() - 16.0, 10.0)),
+                0.0,
+                Color32::GREEN,
+            );
+
+            if region_first {
+                ui.painter().add(region);
+                ui.painter().add(stripe);
+            } else {
+                ui.painter().add(stripe);
+                ui.painter().add(region);
+            }
+        });
+}
+
+/// A popup and a tooltip over part of it.
+fn popup_and_tooltip(region_first: bool, marked: bool) -> impl FnMut(&egui::Context) {
+    move |ctx| {
+        paint_backdrop(ctx);
+
+        let popup = Rect::from_min_size(pos2(20.0, 20.0), vec2(100.0, 70.0));
+        let tooltip = Rect::from_min_size(pos2(70.0, 50.0), vec2(90.0, 50.0));
+        blurred_area(ctx, "popup", Order::Foreground, popup, region_first);
+        blurred_area(ctx, "tooltip", Order::Tooltip, tooltip, region_first);
+
+        if marked {
+            mark_layers(ctx);
+        }
+    }
+}
+
+#[test]
+fn regions_are_painted_at_the_bottom_of_their_layer() {
+    let mut harness = Harness::new(180, 120);
+
+    let expected = harness.run(popup_and_tooltip(true, true));
+    let painted_last = harness.run(popup_and_tooltip(false, true));
+    assert_similar("layers_popup_tooltip", &painted_last, &expected, 0);
+
+    // unmarked, the stripes are blurred into the regions painted over them
+    let unmarked = harness.run(popup_and_tooltip(false, false));
+    assert_ne!(unmarked, expected);
+}
+
+/// Two overlapping regions on one area.
+fn overlapping_regions(a: bool, b: bool) -> impl FnMut(&egui::Context) {
+    move |ctx| {
+        paint_backdrop(ctx);
+
+        Area::new("regions").fixed_pos(Pos2::ZERO).show(ctx, |ui| {
+            let layer = ui.layer_id();
+
+            if a {
+                ui.painter().add(Shape::Callback(layer_blur_callback(
+                    layer,
+                    region_a(),
+                    Rounding::same(8.0),
+                    [BackdropFilter::Blur(6.0), BackdropFilter::Brightness(1.4)],
+                )));
+            }
+            if b {
+                ui.painter().add(Shape::Callback(layer_blur_callback(
+                    layer,
+                    region_b(),
+                    Rounding::same(8.0),
+                    [BackdropFilter::KawaseBlur(8.0), BackdropFilter::Invert(1.0)],
+                )));
+            }
+        });
+
+        mark_layers(ctx);
+    }
+}
+
+fn region_a() -> Rect {
+    Rect::from_min_max(pos2(20.0, 20.0), pos2(90.0, 80.0))
+}
+
+fn region_b() -> Rect {
+    Rect::from_min_max(pos2(60.0, 40.0), pos2(140.0, 100.0))
+}
+
+/// Checks the pixels of `image` outside `rect` match `expected`.
+fn assert_same_outside(image: &RgbaImage, expected: &RgbaImage, rect: Rect) {
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if !rect.expand(1.0).contains(pos2(x as f32, y as f32)) {
+            assert_eq!(pixel, expected.get_pixel(x, y), "{x}, {y}");
+        }
+    }
+}
+
+#[test]
+fn regions_of_a_layer_do_not_see_each_other() {
+    let mut harness = Harness::new(160, 120);
+
+    let both = harness.run(overlapping_regions(true, true));
+    let a = harness.run(overlapping_regions(true, false));
+    let b = harness.run(overlapping_regions(false, true));
+
+    assert_same_outside(&both, &a, region_b());
+    assert_same_outside(&both, &b, region_a());
+}
+
+fn overlapping_windows(lower: bool, marked: bool) -> impl FnMut(&egui::Context) {
+    move |ctx| {
+        paint_backdrop(ctx);
+
+        let windows = [("Lower", pos2(20.0, 20.0)), ("Upper", pos2(80.0, 50.0))];
+
+        for (title, pos) in windows.into_iter().skip(usize::from(!lower)) {
+            BlurWindow::new(title)
+                .blur([BackdropFilter::Blur(10.0)])
+                .window(|window| window.fixed_pos(pos).fixed_size(vec2(100.0, 60.0)))
+                .show(ctx, |ui| ui.label(title));
+        }
+
+        if marked {
+            mark_layers(ctx);
+        }
+    }
+}
+
+#[test]
+fn windows_blur_the_windows_below() {
+    let mut harness = Harness::new(220, 160);
+
+    // the regions are at the bottom of the windows' layers already
+    let marked = harness.run(overlapping_windows(true, true));
+    let unmarked = harness.run(overlapping_windows(true, false));
+    assert_similar("layers_windows", &marked, &unmarked, 0);
+
+    // where they overlap, the upper window's backdrop has the lower one in it
+    let upper = harness.run(overlapping_windows(false, true));
+    assert_ne!(marked.get_pixel(110, 70), upper.get_pixel(110, 70));
+}
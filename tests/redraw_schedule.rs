@@ -0,0 +1,159 @@
+//! The demo's redraw schedule, which only wakes it up for egui's repaints
+//! and, if they're watched, to poll the shader sources.
+
+#[path = "../examples/demo/schedule.rs"]
+mod schedule;
+
+use std::time::{Duration, Instant};
+
+use schedule::{Due, RedrawSchedule};
+use winit::{event::Event, event_loop::ControlFlow, window::WindowId};
+
+const POLL: Duration = Duration::from_millis(250);
+
+/// One iteration of winit's loop at `now`, in the order it sends the events
+/// in, drawing a frame which asks for `repaint_after` if one is due. Returns
+/// whether one was drawn.
+fn loop_iteration(
+    schedule: &mut RedrawSchedule,
+    now: Instant,
+    repaint_after: Duration,
+    control_flow: &mut ControlFlow,
+) -> bool {
+    let events = [
+        Event::NewEvents(winit::event::StartCause::Poll),
+        Event::MainEventsCleared,
+        // only if a redraw was requested
+        Event::RedrawRequested(unsafe { WindowId::dummy() }),
+        Event::RedrawEventsCleared,
+    ];
+
+    let mut redraw = false;
+    let mut drawn = false;
+
+    for event in events {
+        if let Event::RedrawRequested(_) = event {
+            if !redraw {
+                continue;
+            }
+
+            schedule.frame_drawn(now, repaint_after);
+            drawn = true;
+        }
+
+        let due: Due = schedule.on_event::<()>(&event, now, control_flow);
+        redraw |= due.repaint;
+    }
+
+    drawn
+}
+
+#[test]
+fn animations_keep_the_loop_awake() {
+    let start = Instant::now();
+    let mut schedule = RedrawSchedule::new(start, None);
+    let mut control_flow = ControlFlow::Wait;
+
+    // egui asks for the next frame right away while it animates
+    assert!(loop_iteration(
+        &mut schedule,
+        start,
+        Duration::ZERO,
+        &mut control_flow
+    ));
+    assert_eq!(control_flow, ControlFlow::WaitUntil(start));
+
+    let next = start + Duration::from_millis(16);
+    assert!(loop_iteration(
+        &mut schedule,
+        next,
+        Duration::from_millis(100),
+        &mut control_flow
+    ));
+    assert_eq!(
+        control_flow,
+        ControlFlow::WaitUntil(next + Duration::from_millis(100))
+    );
+
+    // woken up early by input, the frame isn't due yet
+    let early = next + Duration::from_millis(50);
+    assert!(!loop_iteration(
+        &mut schedule,
+        early,
+        Duration::MAX,
+        &mut control_flow
+    ));
+    assert_eq!(
+        control_flow,
+        ControlFlow::WaitUntil(next + Duration::from_millis(100))
+    );
+
+    // once the animation is over the loop only waits for input
+    let due = next + Duration::from_millis(100);
+    assert!(loop_iteration(
+        &mut schedule,
+        due,
+        Duration::MAX,
+        &mut control_flow
+    ));
+    assert_eq!(control_flow, ControlFlow::Wait);
+}
+
+#[test]
+fn exiting_loops_are_left_to_exit() {
+    let start = Instant::now();
+    let mut schedule = RedrawSchedule::new(start, Some(POLL));
+    let mut control_flow = ControlFlow::Exit;
+
+    loop_iteration(&mut schedule, start, Duration::ZERO, &mut control_flow);
+    assert_eq!(control_flow, ControlFlow::Exit);
+}
+
+#[test]
+fn idle_loop_only_waits_for_input() {
+    let start = Instant::now();
+    let mut schedule = RedrawSchedule::new(start, None);
+
+    assert!(schedule.repaint_due(start), "first frame");
+    schedule.frame_drawn(start, Duration::MAX);
+
+    assert_eq!(schedule.wake_up(), None);
+    assert!(!schedule.repaint_due(start + Duration::from_secs(3600)));
+    assert!(!schedule.shader_poll_due(start + Duration::from_secs(3600)));
+}
+
+#[test]
+fn repaints_are_drawn_once_due() {
+    let start = Instant::now();
+    let mut schedule = RedrawSchedule::new(start, None);
+    schedule.repaint_due(start);
+
+    let after = Duration::from_millis(100);
+    schedule.frame_drawn(start, after);
+    assert_eq!(schedule.wake_up(), Some(start + after));
+
+    assert!(!schedule.repaint_due(start + after / 2));
+    assert!(schedule.repaint_due(start + after));
+    assert!(!schedule.repaint_due(start + after), "drawn twice");
+    assert_eq!(schedule.wake_up(), None);
+}
+
+#[test]
+fn shaders_are_polled_once_per_interval() {
+    let start = Instant::now();
+    let mut schedule = RedrawSchedule::new(start, Some(POLL));
+    schedule.repaint_due(start);
+    schedule.frame_drawn(start, Duration::MAX);
+
+    // input wakes the loop up far more often than that
+    let polls = (0..100)
+        .map(|ms| start + Duration::from_millis(ms * 10))
+        .filter(|&now| schedule.shader_poll_due(now))
+        .count();
+    assert_eq!(polls, 3, "polled at 250, 500 and 750ms");
+
+    assert_eq!(
+        schedule.wake_up(),
+        Some(start + Duration::from_millis(750) + POLL)
+    );
+}
@@ -0,0 +1,353 @@
+mod common;
+
+use common::{assert_similar, paint_backdrop, Harness};
+use egui::{pos2, Color32, Rect, Rounding, Shape};
+use egui_blur::{
+    blur_callback, layer_blur_callback, mark_layers, reference, BackdropFilter, BlurParams,
+    ColorMatrix,
+};
+
+fn paint_blur(
+    ctx: &egui::Context,
+    rect: Rect,
+    rounding: Rounding,
+    filters: impl Into<Vec<BackdropFilter>>,
+) {
+    ctx.layer_painter(egui::LayerId::background())
+        .add(Shape::Callback(blur_callback(rect, rounding, filters)));
+}
+
+/// Renders the backdrop with and without the blur over it and checks the
+/// blurred frame against the reference applied to the unblurred one.
+fn assert_matches_reference(
+    name: &str,
+    pixels_per_point: f64,
+    filters: impl Into<Vec<BackdropFilter>>,
+    tolerance: u8,
+) {
+    let filters = filters.into();
+    let rect = Rect::from_min_max(pos2(30.0, 20.0), pos2(150.0, 90.0));
+    let rounding = Rounding {
+        nw: 16.0,
+        ne: 4.0,
+        sw: 0.0,
+        se: 24.0,
+    };
+
+    let mut harness = Harness::with_scale(360, 220, pixels_per_point);
+    let backdrop = harness.run(paint_backdrop);
+    let blurred = harness.run(|ctx| {
+        paint_backdrop(ctx);
+        paint_blur(ctx, rect, rounding, filters.clone());
+    });
+
+    let expected =
+        reference::backdrop(&backdrop, rect, rounding, &filters, pixels_per_point as f32);
+
+    assert_similar(name, &blurred, &expected, tolerance);
+}
+
+#[test]
+fn kernel_is_normalized() {
+    for radius in [0.5, 3.0, 27.0, 80.0] {
+        let kernel = reference::kernel(radius);
+        let total = kernel[0] + 2.0 * kernel[1..].iter().sum::<f32>();
+
+        assert!(
+            (total - 1.0).abs() < 1e-5,
+            "radius {radius}: sums to {total}"
+        );
+    }
+}
+
+#[test]
+fn gaussian_matches_reference() {
+    assert_matches_reference("gaussian_reference", 1.0, BlurParams::default(), 2);
+}
+
+#[test]
+fn gaussian_matches_reference_scaled() {
+    assert_matches_reference(
+        "gaussian_reference_scaled",
+        2.0,
+        BlurParams {
+            radius: 12.0,
+            tint: Color32::from_rgb(250, 180, 60),
+            tint_strength: 0.3,
+            opacity: 0.8,
+            ..Default::default()
+        },
+        2,
+    );
+}
+
+#[test]
+fn color_matrix_matches_reference() {
+    assert_matches_reference(
+        "color_matrix_reference",
+        1.0,
+        BlurParams {
+            radius: 9.0,
+            color_matrix: ColorMatrix::saturate(1.8)
+                .then(ColorMatrix::contrast(1.2))
+                .then(ColorMatrix::hue_rotate(40.0)),
+            ..Default::default()
+        },
+        2,
+    );
+}
+
+/// The blur the separable gaussian replaced, a 2D kernel of a standard
+/// deviation of 9 points with taps up to three of them away, summed in linear
+/// light without normalizing them.
+fn original_2d_kernel(image: &image::RgbaImage, rect: Rect) -> image::RgbaImage {
+    use egui::ecolor::{gamma_u8_from_linear_f32, linear_f32_from_gamma_u8};
+
+    const SIGMA: f32 = 9.0;
+    let k = 2.0 * SIGMA * SIGMA;
+    let size = (SIGMA * 3.0) as i32;
+
+    let mut out = image.clone();
+    for y in rect.min.y as i32..rect.max.y as i32 {
+        for x in rect.min.x as i32..rect.max.x as i32 {
+            let mut rgb = [0.0; 3];
+
+            for j in -size..=size {
+                for i in -size..=size {
+                    let weight = (-((i * i + j * j) as f32) / k).exp() / (std::f32::consts::PI * k);
+                    let texel = image.get_pixel((x + i) as u32, (y + j) as u32).0;
+
+                    for (sum, channel) in rgb.iter_mut().zip(texel) {
+                        *sum += weight * linear_f32_from_gamma_u8(channel);
+                    }
+                }
+            }
+
+            let [r, g, b] = rgb.map(gamma_u8_from_linear_f32);
+            out.put_pixel(x as u32, y as u32, image::Rgba([r, g, b, 255]));
+        }
+    }
+
+    out
+}
+
+#[test]
+fn gaussian_matches_the_original_2d_kernel() {
+    // far enough from the edges for the kernel to stay within the image
+    let rect = Rect::from_min_max(pos2(40.0, 40.0), pos2(160.0, 100.0));
+
+    let mut harness = Harness::new(200, 140);
+    let backdrop = harness.run(paint_backdrop);
+    let blurred = harness.run(|ctx| {
+        paint_backdrop(ctx);
+        paint_blur(ctx, rect, Rounding::none(), [BackdropFilter::Blur(27.0)]);
+    });
+
+    // the old kernel's taps sum to a bit less than one, which only darkens
+    // it by a fraction of a level
+    assert_similar(
+        "original_2d_kernel",
+        &blurred,
+        &original_2d_kernel(&backdrop, rect),
+        1,
+    );
+}
+
+#[test]
+fn flat_colour_is_unchanged() {
+    let gray = Color32::from_gray(128);
+    let paint_flat = |ctx: &egui::Context| {
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().fill(gray))
+            .show(ctx, |_| {});
+    };
+
+    let mut harness = Harness::new(160, 120);
+    let flat = harness.run(paint_flat);
+    let blurred = harness.run(|ctx| {
+        paint_flat(ctx);
+        paint_blur(
+            ctx,
+            Rect::from_min_max(pos2(20.0, 20.0), pos2(140.0, 100.0)),
+            Rounding::none(),
+            BlurParams {
+                radius: 40.0,
+                ..Default::default()
+            },
+        );
+    });
+
+    assert_similar("flat_colour", &blurred, &flat, 1);
+}
+
+#[test]
+fn colours_are_clamped_between_colour_filters() {
+    use BackdropFilter::*;
+
+    let color = Color32::from_rgb(200, 60, 40);
+    let paint_flat = |ctx: &egui::Context| {
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().fill(color))
+            .show(ctx, |_| {});
+    };
+
+    let image = Harness::new(160, 120).run(|ctx| {
+        paint_flat(ctx);
+        paint_blur(
+            ctx,
+            Rect::from_min_max(pos2(20.0, 20.0), pos2(140.0, 100.0)),
+            Rounding::none(),
+            [Blur(6.0), Saturate(3.0), Brightness(0.5)],
+        );
+    });
+
+    // as CSS does it, out of range after the saturation, then halved
+    let gamma = [color.r(), color.g(), color.b()].map(|c| c as f32 / 255.0);
+    let saturated = egui_blur::ColorMatrix::saturate(3.0)
+        .rows
+        .map(|[r, g, b, offset]| {
+            (r * gamma[0] + g * gamma[1] + b * gamma[2] + offset).clamp(0.0, 1.0)
+        });
+    let expected = saturated.map(|c| (c * 0.5 * 255.0).round() as u8);
+
+    let pixel = image.get_pixel(80, 60).0;
+    for (channel, expected) in pixel.into_iter().zip(expected) {
+        assert!(
+            channel.abs_diff(expected) <= 1,
+            "{pixel:?} rather than {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn filter_chain_matches_reference() {
+    use BackdropFilter::*;
+
+    // the second blur reads around the rect what the first left in the
+    // ping-pong textures. Every stage rounds to 8 bits, so the GPU and the
+    // reference drift apart by about one more with each.
+    assert_matches_reference(
+        "filter_chain_reference",
+        1.0,
+        [
+            Blur(6.0),
+            Saturate(1.4),
+            Tint(Color32::from_rgb(60, 120, 250), 0.2),
+            Blur(4.0),
+            Contrast(1.1),
+            Noise(0.05),
+            Opacity(0.9),
+        ],
+        3,
+    );
+}
+
+#[test]
+fn unused_filters_are_skipped() {
+    use BackdropFilter::*;
+
+    let rect = Rect::from_min_max(pos2(20.0, 20.0), pos2(140.0, 100.0));
+    let render = |filters: Vec<BackdropFilter>| {
+        Harness::new(160, 120).run(|ctx| {
+            paint_backdrop(ctx);
+            paint_blur(ctx, rect, Rounding::none(), filters.clone());
+        })
+    };
+
+    let blurred = render(vec![Blur(8.0)]);
+    let with_unused = render(vec![
+        Saturate(1.0),
+        Blur(8.0),
+        Blur(0.0),
+        Tint(Color32::RED, 0.0),
+        HueRotate(0.0),
+        Noise(0.0),
+        Opacity(1.0),
+    ]);
+
+    assert_similar("unused_filters", &with_unused, &blurred, 0);
+}
+
+#[test]
+fn clipped_blur_matches_unclipped() {
+    use BackdropFilter::*;
+
+    let rect = Rect::from_min_max(pos2(30.0, 20.0), pos2(150.0, 90.0));
+    let clip_rect = Rect::from_min_max(pos2(70.0, 0.0), pos2(120.0, 60.0));
+    let filters = [KawaseBlur(10.0), Saturate(1.4), Blur(6.0)];
+
+    let mut harness = Harness::new(180, 110);
+    let backdrop = harness.run(paint_backdrop);
+    let unclipped = harness.run(|ctx| {
+        paint_backdrop(ctx);
+        paint_blur(ctx, rect, Rounding::same(8.0), filters);
+    });
+    let clipped = harness.run(|ctx| {
+        paint_backdrop(ctx);
+        ctx.layer_painter(egui::LayerId::background())
+            .with_clip_rect(clip_rect)
+            .add(Shape::Callback(blur_callback(
+                rect,
+                Rounding::same(8.0),
+                filters,
+            )));
+    });
+
+    // the stages only cover what's visible, which is all the composite reads
+    let expected = image::RgbaImage::from_fn(180, 110, |x, y| {
+        let inside = clip_rect.contains(pos2(x as f32 + 0.5, y as f32 + 0.5));
+
+        *if inside { &unclipped } else { &backdrop }.get_pixel(x, y)
+    });
+
+    assert_similar("clipped_blur", &clipped, &expected, 0);
+}
+
+#[test]
+fn stacked_regions_match_reference() {
+    use BackdropFilter::*;
+
+    // from the bottom up, each over parts of the ones below
+    let regions = [
+        (
+            Rect::from_min_max(pos2(20.0, 20.0), pos2(150.0, 110.0)),
+            vec![Blur(6.0)],
+        ),
+        (
+            Rect::from_min_max(pos2(90.0, 60.0), pos2(230.0, 160.0)),
+            vec![Blur(5.0), Saturate(1.4)],
+        ),
+        (
+            Rect::from_min_max(pos2(60.0, 90.0), pos2(180.0, 190.0)),
+            vec![Blur(8.0), Brightness(0.8)],
+        ),
+    ];
+    let rounding = Rounding::same(10.0);
+
+    let mut harness = Harness::new(260, 210);
+    let backdrop = harness.run(paint_backdrop);
+    let stacked = harness.run(|ctx| {
+        paint_backdrop(ctx);
+
+        for (i, (rect, filters)) in regions.iter().enumerate() {
+            egui::Area::new(egui::Id::new(("stacked", i)))
+                .fixed_pos(rect.min)
+                .show(ctx, |ui| {
+                    ui.painter().add(Shape::Callback(layer_blur_callback(
+                        ui.layer_id(),
+                        *rect,
+                        rounding,
+                        filters.clone(),
+                    )));
+                });
+        }
+
+        mark_layers(ctx);
+    });
+
+    let expected = regions.iter().fold(backdrop, |image, (rect, filters)| {
+        reference::backdrop(&image, *rect, rounding, filters, 1.0)
+    });
+
+    assert_similar("stacked_reference", &stacked, &expected, 3);
+}
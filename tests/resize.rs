@@ -0,0 +1,88 @@
+mod common;
+
+use common::{assert_similar, paint_backdrop, Harness};
+use egui::{pos2, Rect, Rounding, Shape};
+use egui_blur::{blur_callback, BackdropFilter, WindowTexture, MAX_KAWASE_LEVELS};
+
+/// Every render pipeline of the window texture, which tell whether they were
+/// created again.
+fn pipeline_ids(window_texture: &WindowTexture) -> Vec<wgpu::Id<wgpu::RenderPipeline>> {
+    let registry = window_texture.pipeline_registry();
+
+    [
+        &registry.copy_pipeline,
+        &registry.blur_horizontal_pipeline,
+        &registry.blur_vertical_pipeline,
+        &registry.kawase_downsample_pipeline,
+        &registry.kawase_upsample_pipeline,
+        &registry.kawase_upsample_final_pipeline,
+        &registry.color_pipeline,
+        &registry.composite_pipeline,
+        &registry.mask_pipeline,
+        &registry.composite_mask_pipeline,
+    ]
+    .into_iter()
+    .chain(&registry.custom_pipelines)
+    .map(wgpu::RenderPipeline::global_id)
+    .collect()
+}
+
+/// Sizes of the window texture, the ping-pong textures and the kawase
+/// levels.
+fn texture_sizes(window_texture: &WindowTexture) -> Vec<(u32, u32)> {
+    let size = |texture: &wgpu::Texture| (texture.width(), texture.height());
+
+    [window_texture.texture()]
+        .into_iter()
+        .chain((0..2).map(|i| window_texture.ping_pong_texture(i)))
+        .chain((0..MAX_KAWASE_LEVELS).map(|level| window_texture.kawase_texture(level)))
+        .map(size)
+        .collect()
+}
+
+fn render(harness: &mut Harness) -> image::RgbaImage {
+    harness.run(|ctx| {
+        paint_backdrop(ctx);
+        ctx.layer_painter(egui::LayerId::background())
+            .add(Shape::Callback(blur_callback(
+                Rect::from_min_max(pos2(20.0, 20.0), pos2(140.0, 100.0)),
+                Rounding::same(12.0),
+                [
+                    BackdropFilter::KawaseBlur(12.0),
+                    BackdropFilter::Saturate(1.5),
+                ],
+            )));
+    })
+}
+
+#[test]
+fn resizing_keeps_the_pipelines() {
+    let mut harness = Harness::new(160, 120);
+    render(&mut harness);
+
+    let pipelines = pipeline_ids(harness.window_texture());
+
+    for (width, height) in [(200, 150), (96, 180), (160, 120), (240, 200)] {
+        let texture = harness.window_texture().texture().global_id();
+
+        harness.resize(width, height);
+        let window_texture = harness.window_texture();
+
+        assert_eq!(pipeline_ids(window_texture), pipelines, "{width}x{height}");
+        assert_ne!(window_texture.texture().global_id(), texture);
+
+        let levels = (1..=MAX_KAWASE_LEVELS as u32)
+            .map(|level| ((width >> level).max(1), (height >> level).max(1)));
+        let expected: Vec<_> = [(width, height); 3].into_iter().chain(levels).collect();
+        assert_eq!(texture_sizes(window_texture), expected, "{width}x{height}");
+
+        render(&mut harness);
+    }
+
+    // and renders what a window texture created at that size does, the
+    // adapter not being shared between two contexts at a time
+    let resized = render(&mut harness);
+    drop(harness);
+
+    assert_similar("resized", &resized, &render(&mut Harness::new(240, 200)), 0);
+}
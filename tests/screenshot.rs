@@ -0,0 +1,44 @@
+mod common;
+
+use common::{assert_similar, paint_backdrop, Harness, FORMAT};
+use egui::{pos2, Rect, Rounding, Shape};
+use egui_blur::{blur_callback, BlurParams, RenderTarget};
+use winit::dpi::PhysicalSize;
+
+fn paint_scene(ctx: &egui::Context) {
+    paint_backdrop(ctx);
+
+    ctx.layer_painter(egui::LayerId::background())
+        .add(Shape::Callback(blur_callback(
+            Rect::from_min_max(pos2(20.0, 20.0), pos2(120.0, 80.0)),
+            Rounding::same(8.0),
+            BlurParams::default(),
+        )));
+}
+
+#[test]
+fn bgra_reads_back_as_rgba() {
+    let target = |format| RenderTarget {
+        format,
+        size: PhysicalSize::new(150, 100),
+        pixels_per_point: 1.0,
+    };
+
+    let rgba = Harness::with_target(target(FORMAT)).run(paint_scene);
+    let bgra = Harness::with_target(target(wgpu::TextureFormat::Bgra8UnormSrgb)).run(paint_scene);
+
+    assert_similar("bgra_read_back", &bgra, &rgba, 0);
+}
+
+#[test]
+fn window_texture_reads_back_as_drawn() {
+    let mut harness = Harness::new(150, 100);
+    let drawn = harness.run(paint_scene);
+
+    assert_similar(
+        "window_texture_read_back",
+        &harness.read_back_window_texture(),
+        &drawn,
+        0,
+    );
+}
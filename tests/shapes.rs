@@ -0,0 +1,198 @@
+mod common;
+
+use common::{assert_similar, paint_backdrop, Harness};
+use egui::{pos2, vec2, Color32, LayerId, Order, Pos2, Rect, Rounding, Shape, Stroke};
+use egui_blur::{
+    blur_callback, layer_blur_callback, layer_shape_blur_callback, mark_layers,
+    shape_blur_callback, BackdropFilter,
+};
+
+const BLUR: [BackdropFilter; 1] = [BackdropFilter::Blur(8.0)];
+
+const CENTER: Pos2 = pos2(80.0, 60.0);
+const RADIUS: f32 = 40.0;
+
+/// The backdrop with `region` painted over it on the background.
+fn backdrop_with(region: Option<Shape>) -> impl FnMut(&egui::Context) {
+    move |ctx| {
+        paint_backdrop(ctx);
+
+        if let Some(region) = region.clone() {
+            ctx.layer_painter(LayerId::background()).add(region);
+        }
+    }
+}
+
+#[test]
+fn shapes_cut_the_backdrop() {
+    let mut harness = Harness::new(160, 120);
+    let bounds = Rect::from_center_size(CENTER, vec2(RADIUS, RADIUS) * 2.0);
+
+    let backdrop = harness.run(backdrop_with(None));
+    let rect = harness.run(backdrop_with(Some(Shape::Callback(blur_callback(
+        bounds,
+        Rounding::none(),
+        BLUR,
+    )))));
+    let circle = harness.run(backdrop_with(Some(Shape::Callback(shape_blur_callback(
+        Shape::circle_filled(CENTER, RADIUS, Color32::WHITE),
+        BLUR,
+    )))));
+
+    for (x, y, pixel) in circle.enumerate_pixels() {
+        let distance = pos2(x as f32 + 0.5, y as f32 + 0.5).distance(CENTER);
+
+        if distance < RADIUS - 1.0 {
+            assert_eq!(pixel, rect.get_pixel(x, y), "inside at {x}, {y}");
+        } else if distance > RADIUS + 1.0 {
+            assert_eq!(pixel, backdrop.get_pixel(x, y), "outside at {x}, {y}");
+        }
+    }
+
+    // antialiased, the edge blends the two
+    let y = (CENTER.y - RADIUS) as u32;
+    let blends = (60..100).any(|x| {
+        let [edge, inside, outside] =
+            [&circle, &rect, &backdrop].map(|image| image.get_pixel(x, y).0);
+
+        (0..3).any(|c| {
+            let (low, high) = (inside[c].min(outside[c]), inside[c].max(outside[c]));
+            low < edge[c] && edge[c] < high
+        })
+    });
+    assert!(blends, "no partially covered pixels on the edge");
+}
+
+#[test]
+fn rounded_rect_shapes_match_rounded_regions() {
+    let mut harness = Harness::new(160, 120);
+    let rect = Rect::from_min_max(pos2(20.0, 20.0), pos2(140.0, 90.0));
+    let rounding = Rounding::same(16.0);
+
+    let region = harness.run(backdrop_with(Some(Shape::Callback(blur_callback(
+        rect, rounding, BLUR,
+    )))));
+    let shape = harness.run(backdrop_with(Some(Shape::Callback(shape_blur_callback(
+        Shape::rect_filled(rect, rounding, Color32::WHITE),
+        BLUR,
+    )))));
+
+    // the edges are antialiased a little differently
+    assert_similar("shape_rounded_rect", &shape, &region, 3);
+}
+
+/// What the ring of [`strokes_on_layers_are_masks_too`] is compared with.
+#[derive(Clone, Copy)]
+enum Region {
+    None,
+    Ring,
+    Bounds,
+}
+
+#[test]
+fn strokes_on_layers_are_masks_too() {
+    let mut harness = Harness::new(160, 120);
+    let ring = Shape::circle_stroke(CENTER, RADIUS, Stroke::new(12.0, Color32::WHITE));
+    let bounds = ring.visual_bounding_rect();
+
+    let mut run = |region: Region| {
+        let ring = ring.clone();
+
+        harness.run(move |ctx| {
+            paint_backdrop(ctx);
+
+            egui::Area::new("ring")
+                .order(Order::Foreground)
+                .fixed_pos(Pos2::ZERO)
+                .show(ctx, |ui| {
+                    let layer = ui.layer_id();
+                    let region = match region {
+                        Region::None => return,
+                        Region::Ring => layer_shape_blur_callback(layer, ring.clone(), BLUR),
+                        Region::Bounds => {
+                            layer_blur_callback(layer, bounds, Rounding::none(), BLUR)
+                        }
+                    };
+                    ui.painter().add(Shape::Callback(region));
+                });
+
+            mark_layers(ctx);
+        })
+    };
+
+    let backdrop = run(Region::None);
+    let ring_image = run(Region::Ring);
+    let bounds_image = run(Region::Bounds);
+
+    // the ring shows the backdrop blurred as a region over its bounds does,
+    // and leaves it as it is in the middle
+    let (x, y) = (CENTER.x as u32, (CENTER.y - RADIUS) as u32);
+    assert_eq!(ring_image.get_pixel(x, y), bounds_image.get_pixel(x, y));
+    assert_ne!(ring_image.get_pixel(x, y), backdrop.get_pixel(x, y));
+
+    let (x, y) = (CENTER.x as u32, CENTER.y as u32);
+    assert_eq!(ring_image.get_pixel(x, y), backdrop.get_pixel(x, y));
+    assert_ne!(bounds_image.get_pixel(x, y), backdrop.get_pixel(x, y));
+}
+
+#[test]
+fn overlapping_triangles_cover_once() {
+    let mut harness = Harness::new(160, 120);
+    let circle = Shape::circle_filled(CENTER, RADIUS, Color32::WHITE);
+    let filters = [BackdropFilter::Blur(8.0), BackdropFilter::Opacity(0.5)];
+
+    let once = harness.run(backdrop_with(Some(Shape::Callback(shape_blur_callback(
+        circle.clone(),
+        filters,
+    )))));
+    let twice = harness.run(backdrop_with(Some(Shape::Callback(shape_blur_callback(
+        Shape::Vec(vec![circle.clone(), circle]),
+        filters,
+    )))));
+
+    assert_similar("shape_overlapping", &twice, &once, 0);
+}
+
+#[test]
+fn masks_of_many_regions_fit() {
+    let mut harness = Harness::new(320, 192);
+    // enough vertices and indices for both buffers to grow
+    let rects: Vec<Rect> = (0..10)
+        .flat_map(|x| (0..6).map(move |y| pos2(5.0 + x as f32 * 32.0, 5.0 + y as f32 * 32.0)))
+        .map(|min| Rect::from_min_size(min, vec2(22.0, 22.0)))
+        .collect();
+
+    let mut run = |shapes: bool| {
+        let rects = rects.clone();
+
+        harness.run(move |ctx| {
+            paint_backdrop(ctx);
+
+            let painter = ctx.layer_painter(LayerId::background());
+            for &rect in &rects {
+                let region = if shapes {
+                    let shape = Shape::rect_filled(rect, Rounding::none(), Color32::WHITE);
+                    shape_blur_callback(shape, BLUR)
+                } else {
+                    blur_callback(rect, Rounding::none(), BLUR)
+                };
+                painter.add(Shape::Callback(region));
+            }
+        })
+    };
+
+    let regions = run(false);
+    let shapes = run(true);
+
+    // the edges are antialiased differently, everything else is the same
+    for (x, y, pixel) in shapes.enumerate_pixels() {
+        let pos = pos2(x as f32 + 0.5, y as f32 + 0.5);
+        let on_edge = rects
+            .iter()
+            .any(|rect| rect.expand(1.0).contains(pos) && !rect.shrink(1.0).contains(pos));
+
+        if !on_edge {
+            assert_eq!(pixel, regions.get_pixel(x, y), "at {x}, {y}");
+        }
+    }
+}